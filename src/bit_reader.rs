@@ -0,0 +1,27 @@
+pub(crate) struct BitReader<'a> {
+  data: &'a [u8],
+  position: usize,
+}
+
+impl<'a> BitReader<'a> {
+  pub(crate) fn new(data: &'a [u8]) -> Self {
+    Self { data, position: 0 }
+  }
+
+  pub(crate) fn read(&mut self, count: u32) -> Option<u32> {
+    let count = count as usize;
+    if self.position + count > self.data.len() * 8 {
+      return None;
+    }
+
+    let mut value = 0;
+    for index in 0..count {
+      let position = self.position + index;
+      let bit = (self.data[position / 8] >> (position % 8)) & 1;
+      value |= u32::from(bit) << index;
+    }
+    self.position += count;
+
+    Some(value)
+  }
+}
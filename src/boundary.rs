@@ -0,0 +1,286 @@
+use super::{CompressionMethod, DataDescriptor, LocalFileHeader, LzmaHeader};
+use crate::prelude::*;
+use crate::search::Search;
+use flate2::{Decompress, FlushDecompress, Status};
+use std::io::{BufReader, SeekFrom};
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+pub(crate) fn find_end_of_data<R: Read + Seek>(reader: &mut R, compression_method: u16, lzma_end_of_stream_marker: bool, zip64: bool) -> Result<u64> {
+  let initial_stream_position = reader.stream_position()?;
+
+  let compressed_size = match CompressionMethod::from(compression_method) {
+    CompressionMethod::Stored => search_for_stored_data_descriptor(reader, zip64)?,
+    CompressionMethod::Deflated => inflate_to_end(reader)?,
+    CompressionMethod::Bzip2 => bunzip_to_end(reader)?,
+    CompressionMethod::Lzma if lzma_end_of_stream_marker => decode_lzma_to_end_of_stream_marker(reader)?,
+    #[cfg(feature = "zstd")]
+    CompressionMethod::Zstd => decode_zstd_to_end_of_frame(reader)?,
+    #[cfg(feature = "xz")]
+    CompressionMethod::Xz => decode_xz_to_end_of_stream(reader)?,
+    _ => search_for_data_descriptor(reader, zip64)?,
+  };
+
+  #[cfg(feature = "logging")]
+  log::debug!("compressed data at {initial_stream_position:#010X} ends after {compressed_size} bytes");
+
+  reader.seek(SeekFrom::Start(initial_stream_position))?;
+  Ok(compressed_size)
+}
+
+pub(crate) fn measure_uncompressed_data<R: Read>(reader: R, header: &LocalFileHeader, compressed_size: u64) -> Result<DataDescriptor> {
+  let mut decoder = CompressionMethod::from(header.compression_method).decoder_with(reader, header, None)?;
+  let mut hasher = crc32fast::Hasher::new();
+  let mut buffer = vec![0u8; BUFFER_SIZE];
+  let mut uncompressed_size = 0;
+
+  loop {
+    let length = match decoder.read(&mut buffer) {
+      Ok(0) => break,
+      Ok(length) => length,
+      Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+      Err(error) => return Err(error.into()),
+    };
+    hasher.update(&buffer[..length]);
+    uncompressed_size += u64::try_from(length)?;
+  }
+
+  Ok(DataDescriptor {
+    signature: None,
+    crc32_of_uncompressed_data: hasher.finalize(),
+    compressed_size,
+    uncompressed_size,
+    zip64: header.indicates_zip64(),
+  })
+}
+
+fn inflate_to_end<R: Read + Seek>(reader: &mut R) -> Result<u64> {
+  let mut decompress = Decompress::new(false);
+  decode_to_end(reader, |input, output| {
+    let (total_in, total_out) = (decompress.total_in(), decompress.total_out());
+    let status = decompress.decompress(input, output, FlushDecompress::None)?;
+    Ok((
+      usize::try_from(decompress.total_in() - total_in)?,
+      usize::try_from(decompress.total_out() - total_out)?,
+      status == Status::StreamEnd,
+    ))
+  })
+}
+
+fn bunzip_to_end<R: Read + Seek>(reader: &mut R) -> Result<u64> {
+  let mut decompress = bzip2::Decompress::new(false);
+  decode_to_end(reader, |input, output| {
+    let (total_in, total_out) = (decompress.total_in(), decompress.total_out());
+    let status = decompress.decompress(input, output).map_err(std::io::Error::from)?;
+    Ok((
+      usize::try_from(decompress.total_in() - total_in)?,
+      usize::try_from(decompress.total_out() - total_out)?,
+      status == bzip2::Status::StreamEnd,
+    ))
+  })
+}
+
+// Feeds the input to `decode` until it reports the end of the stream, and returns how much input that took.
+// `decode` returns how much input it consumed, how much output it produced, and whether the stream has ended.
+fn decode_to_end<R: Read>(reader: &mut R, mut decode: impl FnMut(&[u8], &mut [u8]) -> Result<(usize, usize, bool)>) -> Result<u64> {
+  let mut input = vec![0u8; BUFFER_SIZE];
+  let mut output = vec![0u8; BUFFER_SIZE];
+  let mut total_in = 0;
+
+  loop {
+    let length = reader.read(&mut input)?;
+    if length == 0 {
+      return Err(Error::unexpected_eof());
+    }
+
+    let mut consumed = 0;
+    loop {
+      let (newly_consumed, produced, finished) = decode(&input[consumed..length], &mut output)?;
+      consumed += newly_consumed;
+      if finished {
+        return Ok(total_in + u64::try_from(consumed)?);
+      }
+      if produced == output.len() {
+        continue;
+      }
+      if consumed == length {
+        break;
+      }
+      if newly_consumed == 0 {
+        return Err(std::io::Error::from(std::io::ErrorKind::InvalidData).into());
+      }
+    }
+    total_in += u64::try_from(length)?;
+  }
+}
+
+fn decode_lzma_to_end_of_stream_marker<R: Read + Seek>(reader: &mut R) -> Result<u64> {
+  let header = LzmaHeader::read(reader)?;
+  let start_of_stream = reader.stream_position()?;
+
+  // The decoder stops right after the end-of-stream marker, but rejects the marker when more input follows it, so the
+  // first pass only counts how much input it consumed and the second checks that exactly that much is a complete stream.
+  let mut compressed = Consumed {
+    inner: BufReader::with_capacity(BUFFER_SIZE, reader.by_ref()),
+    consumed: 0,
+  };
+  if let Err(lzma_rs::error::Error::IoError(error) | lzma_rs::error::Error::HeaderTooShort(error)) = decode_lzma(&header, &mut compressed) {
+    return Err(error.into());
+  }
+  let consumed = compressed.consumed;
+
+  reader.seek(SeekFrom::Start(start_of_stream))?;
+  decode_lzma(&header, BufReader::with_capacity(BUFFER_SIZE, reader.by_ref().take(consumed)))
+    .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+  Ok(header.expected_size() + consumed)
+}
+
+fn decode_lzma<B: BufRead>(header: &LzmaHeader, compressed: B) -> lzma_rs::error::Result<()> {
+  let options = lzma_rs::decompress::Options {
+    unpacked_size: lzma_rs::decompress::UnpackedSize::UseProvided(None),
+    ..Default::default()
+  };
+  lzma_rs::lzma_decompress_with_options(&mut header.properties.as_slice().chain(compressed), &mut std::io::sink(), &options)
+}
+
+#[cfg(feature = "zstd")]
+fn decode_zstd_to_end_of_frame<R: Read + Seek>(reader: &mut R) -> Result<u64> {
+  use zstd::stream::raw::{Decoder, InBuffer, Operation, OutBuffer};
+
+  let mut decoder = Decoder::new()?;
+  decode_to_end(reader, |input, output| {
+    let mut compressed = InBuffer::around(input);
+    let mut uncompressed = OutBuffer::around(output);
+    let hint = decoder.run(&mut compressed, &mut uncompressed)?;
+    Ok((compressed.pos(), uncompressed.pos(), hint == 0))
+  })
+}
+
+#[cfg(feature = "xz")]
+fn decode_xz_to_end_of_stream<R: Read + Seek>(reader: &mut R) -> Result<u64> {
+  use xz2::stream::{Action, Status, Stream};
+
+  let mut stream = Stream::new_stream_decoder(u64::MAX, 0).map_err(std::io::Error::from)?;
+  decode_to_end(reader, |input, output| {
+    let (total_in, total_out) = (stream.total_in(), stream.total_out());
+    let status = stream.process(input, output, Action::Run).map_err(std::io::Error::from)?;
+    Ok((
+      usize::try_from(stream.total_in() - total_in)?,
+      usize::try_from(stream.total_out() - total_out)?,
+      status == Status::StreamEnd,
+    ))
+  })
+}
+
+struct Consumed<B> {
+  inner: B,
+  consumed: u64,
+}
+
+impl<B: BufRead> Read for Consumed<B> {
+  fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let length = self.inner.read(buffer)?;
+    self.consumed += length as u64;
+    Ok(length)
+  }
+}
+
+impl<B: BufRead> BufRead for Consumed<B> {
+  fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+    self.inner.fill_buf()
+  }
+
+  fn consume(&mut self, amount: usize) {
+    self.consumed += amount as u64;
+    self.inner.consume(amount);
+  }
+}
+
+fn search_for_data_descriptor<R: Read + Seek>(reader: &mut R, zip64: bool) -> Result<u64> {
+  let initial_stream_position = reader.stream_position()?;
+
+  while let Some(position) = reader.search(&DataDescriptor::SIGNATURE)? {
+    let data_descriptor = match if zip64 { DataDescriptor::read_zip64(reader) } else { DataDescriptor::read(reader) } {
+      Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof => break,
+      result => result?,
+    };
+    let distance = position - initial_stream_position;
+    if data_descriptor.compressed_size == distance {
+      return Ok(distance);
+    }
+
+    #[cfg(feature = "logging")]
+    log::trace!(
+      "ignoring data descriptor candidate at {position:#010X}: compressed size {} != {distance}",
+      data_descriptor.compressed_size
+    );
+
+    reader.seek(SeekFrom::Start(position + 1))?;
+  }
+
+  Err(Error::MissingDataDescriptor)
+}
+
+fn search_for_stored_data_descriptor<R: Read + Seek>(reader: &mut R, zip64: bool) -> Result<u64> {
+  let size_fields = if zip64 { 16 } else { 8 };
+  let read_fields = |mut fields: &[u8]| -> Result<(u32, u64)> {
+    let crc32_of_uncompressed_data = fields.read_u32::<LittleEndian>()?;
+    let compressed_size = if zip64 {
+      fields.read_u64::<LittleEndian>()?
+    } else {
+      fields.read_u32::<LittleEndian>()?.into()
+    };
+    Ok((crc32_of_uncompressed_data, compressed_size))
+  };
+
+  let mut hasher = crc32fast::Hasher::new();
+  let mut buffer = Vec::with_capacity(BUFFER_SIZE + 8 + size_fields);
+  let mut buffer_distance = 0u64;
+  let mut end_of_file = false;
+
+  while !end_of_file {
+    let filled = buffer.len();
+    buffer.resize(filled + BUFFER_SIZE, 0);
+    let length = reader.read(&mut buffer[filled..])?;
+    buffer.truncate(filled + length);
+    end_of_file = length == 0;
+
+    let required = if end_of_file { 4 + size_fields } else { 8 + size_fields };
+    let mut index = 0;
+    while index + required <= buffer.len() {
+      let distance = buffer_distance + u64::try_from(index)?;
+      let candidate = &buffer[index..];
+
+      let with_signature = if candidate.starts_with(&DataDescriptor::SIGNATURE) && candidate.len() >= 8 + size_fields {
+        Some(read_fields(&candidate[4..])?)
+      } else {
+        None
+      };
+      let without_signature = Some(read_fields(candidate)?);
+
+      for (crc32_of_uncompressed_data, compressed_size) in [with_signature, without_signature].into_iter().flatten() {
+        if compressed_size != distance {
+          continue;
+        }
+
+        let mut running = hasher.clone();
+        running.update(&buffer[..index]);
+        if running.finalize() == crc32_of_uncompressed_data {
+          return Ok(distance);
+        }
+
+        #[cfg(feature = "logging")]
+        log::trace!("ignoring data descriptor candidate after {distance} bytes: CRC-32 mismatch");
+      }
+
+      index += 1;
+    }
+
+    hasher.update(&buffer[..index]);
+    buffer.drain(..index);
+    buffer_distance += u64::try_from(index)?;
+  }
+
+  Err(Error::MissingDataDescriptor)
+}
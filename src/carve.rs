@@ -0,0 +1,75 @@
+use super::{CompressionMethod, Entry, LocalFileHeader, ParseOptions};
+use crate::prelude::*;
+use crate::search::Search;
+use std::io::SeekFrom;
+
+const MAXIMUM_VERSION_NEEDED_TO_EXTRACT: u16 = 63;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Carved {
+  pub offset: u64,
+  pub entry: Entry,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Carve {
+  pub entries: Vec<Carved>,
+}
+
+impl Carve {
+  pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    let initial_stream_position = reader.stream_position()?;
+    let length = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(initial_stream_position))?;
+
+    let mut entries = Vec::new();
+    while let Some(offset) = reader.search(&LocalFileHeader::SIGNATURE)? {
+      match Self::read_candidate(reader, length) {
+        Ok(entry) => {
+          #[cfg(feature = "logging")]
+          log::debug!("carved {:?} at {offset:#010X}", entry.header.decoded_file_name());
+          entries.push(Carved { offset, entry });
+        }
+        Err(_error) => {
+          #[cfg(feature = "logging")]
+          log::trace!("rejected candidate at {offset:#010X}: {_error}");
+          reader.seek(SeekFrom::Start(offset + 1))?;
+        }
+      }
+    }
+
+    Ok(Self { entries })
+  }
+
+  fn read_candidate<R: Read + Seek>(reader: &mut R, length: u64) -> Result<Entry> {
+    let offset = reader.stream_position()?;
+
+    let mut options = ParseOptions::lenient();
+    let header = LocalFileHeader::read_with_options(reader, &mut options)?;
+    if !options.diagnostics.is_empty() || !Self::is_plausible(&header, length - offset) {
+      return Err(Error::ImplausibleLocalFileHeader);
+    }
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let entry = Entry::read(reader)?;
+
+    entry.decompress()?;
+
+    Ok(entry)
+  }
+
+  fn is_plausible(header: &LocalFileHeader, remaining: u64) -> bool {
+    let known_compression_method = CompressionMethod::from(header.compression_method).is_supported();
+    let sane_version = header.version_needed_to_extract <= MAXIMUM_VERSION_NEEDED_TO_EXTRACT;
+    let sane_file_name = !header.file_name.is_empty() && !header.effective_file_name().chars().any(char::is_control);
+    let sane_sizes = header.indicates_data_descriptor_is_present()
+      || header
+        .expected_size()
+        .checked_add(header.effective_compressed_size())
+        .is_some_and(|size| size <= remaining)
+        && (CompressionMethod::from(header.compression_method) != CompressionMethod::Stored
+          || header.effective_compressed_size() == header.effective_uncompressed_size());
+
+    known_compression_method && sane_version && sane_file_name && sane_sizes
+  }
+}
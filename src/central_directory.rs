@@ -1,16 +1,24 @@
-use super::{CentralDirectoryFileHeader, EndOfCentralDirectory, Entry};
+use super::{
+  CentralDirectoryFileHeader, EndOfCentralDirectory, FileNameEncoding, LocalEntry, Offsets, ParseOptions, Zip64EndOfCentralDirectory, Zip64EndOfCentralDirectoryLocator,
+};
 use crate::prelude::*;
 use std::io::{Read, Seek, SeekFrom, Write};
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct CentralDirectory {
   pub files: Vec<CentralDirectoryFileHeader>,
+  pub zip64_end: Option<Zip64EndOfCentralDirectory>,
   pub end: EndOfCentralDirectory,
+  pub prefix_length: u64,
 }
 
 impl ExpectedSize for CentralDirectory {
-  fn expected_size(&self) -> u32 {
-    self.files.iter().map(CentralDirectoryFileHeader::expected_size).sum::<u32>() + self.end.expected_size()
+  fn expected_size(&self) -> u64 {
+    let zip64_end = match &self.zip64_end {
+      Some(zip64_end) => zip64_end.expected_size() + Zip64EndOfCentralDirectoryLocator::default().expected_size(),
+      None => 0,
+    };
+    self.files.iter().map(CentralDirectoryFileHeader::expected_size).sum::<u64>() + zip64_end + self.end.expected_size()
   }
 }
 
@@ -19,51 +27,189 @@ impl CentralDirectory {
     Self::default()
   }
 
-  pub fn add(&mut self, entry: &Entry) -> Result<()> {
-    let mut header = entry.header.clone();
-    if header.indicates_data_descriptor_is_present() {
-      #[allow(clippy::clone_on_copy)]
-      if let Some(data_descriptor) = entry.data_descriptor.clone() {
-        header.update(data_descriptor)?;
+  pub fn add<E: LocalEntry>(&mut self, entry: &E) -> Result<()> {
+    self.add_at(entry, self.offset_of_start_of_central_directory())
+  }
+
+  pub fn add_at<E: LocalEntry>(&mut self, entry: &E, relative_offset_of_local_file_header: u64) -> Result<()> {
+    let header = entry.effective_header()?;
+
+    let compressed_size = header.effective_compressed_size();
+    let uncompressed_size = header.effective_uncompressed_size();
+    let mut file = CentralDirectoryFileHeader::from(header);
+    file.set_sizes_and_offset(compressed_size, uncompressed_size, relative_offset_of_local_file_header)?;
+
+    let size_of_central_directory = self.size_of_central_directory() + file.expected_size();
+    // Entries may be added out of order, so the Central Directory starts after whichever one ends last.
+    let offset_of_start_of_central_directory = self
+      .offset_of_start_of_central_directory()
+      .max(relative_offset_of_local_file_header + entry.size_in_archive());
+    self.files.push(file);
+    self.update_end(size_of_central_directory, offset_of_start_of_central_directory)
+  }
+
+  pub fn total_number_of_central_directory_records(&self) -> u64 {
+    match &self.zip64_end {
+      Some(zip64_end) => zip64_end.total_number_of_central_directory_records,
+      None => self.end.total_number_of_central_directory_records.into(),
+    }
+  }
+
+  pub fn size_of_central_directory(&self) -> u64 {
+    match &self.zip64_end {
+      Some(zip64_end) => zip64_end.size_of_central_directory,
+      None => self.end.size_of_central_directory.into(),
+    }
+  }
+
+  pub fn offset_of_start_of_central_directory(&self) -> u64 {
+    match &self.zip64_end {
+      Some(zip64_end) => zip64_end.offset_of_start_of_central_directory_relative_to_start_of_archive,
+      None => self.end.offset_of_start_of_central_directory_relative_to_start_of_archive.into(),
+    }
+  }
+
+  pub fn set_offset_of_start_of_central_directory(&mut self, offset_of_start_of_central_directory: u64) -> Result<()> {
+    let size_of_central_directory = self.files.iter().map(CentralDirectoryFileHeader::expected_size).sum();
+    self.update_end(size_of_central_directory, offset_of_start_of_central_directory)
+  }
+
+  pub fn transcode_file_names(&mut self, encoding: FileNameEncoding) -> Result<()> {
+    let encoding = match encoding {
+      FileNameEncoding::Auto => {
+        let legacy = self
+          .files
+          .iter()
+          .filter(|file| !file.indicates_utf8() && !file.unicode_path().is_some_and(|unicode_path| unicode_path.applies_to(&file.file_name)));
+        FileNameEncoding::detect(legacy.map(|file| file.file_name.as_slice())).encoding
       }
+      encoding => encoding,
+    };
+
+    for file in &mut self.files {
+      file.transcode_to_utf8(encoding)?;
     }
 
-    let mut file = CentralDirectoryFileHeader::from(header);
-    if let Some(last) = self.files.last() {
-      file.relative_offset_of_local_file_header = last.relative_offset_of_local_file_header + last.expected_size();
+    self.set_offset_of_start_of_central_directory(self.offset_of_start_of_central_directory())
+  }
+
+  pub fn to_absolute(&self) -> Result<Self> {
+    let mut value = self.clone();
+    for file in &mut value.files {
+      file.set_relative_offset_of_local_file_header(file.effective_relative_offset_of_local_file_header() + self.prefix_length)?;
+    }
+    value.set_offset_of_start_of_central_directory(self.offset_of_start_of_central_directory() + self.prefix_length)?;
+    value.prefix_length = 0;
+    Ok(value)
+  }
+
+  fn update_end(&mut self, size_of_central_directory: u64, offset_of_start_of_central_directory: u64) -> Result<()> {
+    let number_of_records = u64::try_from(self.files.len())?;
+
+    self.end.number_of_central_directory_records_on_this_disk = number_of_records.try_into().unwrap_or(u16::MAX);
+    self.end.total_number_of_central_directory_records = number_of_records.try_into().unwrap_or(u16::MAX);
+    self.end.size_of_central_directory = size_of_central_directory.try_into().unwrap_or(u32::MAX);
+    self.end.offset_of_start_of_central_directory_relative_to_start_of_archive = offset_of_start_of_central_directory.try_into().unwrap_or(u32::MAX);
+
+    let requires_zip64 = number_of_records >= u16::MAX.into() || size_of_central_directory >= u32::MAX.into() || offset_of_start_of_central_directory >= u32::MAX.into();
+    if requires_zip64 {
+      #[cfg(feature = "logging")]
+      if self.zip64_end.is_none() {
+        log::debug!("Central Directory requires ZIP64 End of Central Directory Record");
+      }
+
+      let zip64_end = self.zip64_end.get_or_insert_with(Zip64EndOfCentralDirectory::default);
+      zip64_end.number_of_central_directory_records_on_this_disk = number_of_records;
+      zip64_end.total_number_of_central_directory_records = number_of_records;
+      zip64_end.size_of_central_directory = size_of_central_directory;
+      zip64_end.offset_of_start_of_central_directory_relative_to_start_of_archive = offset_of_start_of_central_directory;
+    } else {
+      self.zip64_end = None;
     }
 
-    self.files.push(file);
-    self.end.number_of_central_directory_records_on_this_disk = self.files.len().try_into()?;
-    self.end.total_number_of_central_directory_records = self.files.len().try_into()?;
-    self.end.size_of_central_directory = self.files.iter().map(CentralDirectoryFileHeader::expected_size).sum();
-    self.end.offset_of_start_of_central_directory_relative_to_start_of_archive += entry.expected_size();
     Ok(())
   }
 }
 
 impl CentralDirectory {
   pub fn read_from_end<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_from_end_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_from_end_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
     let initial_stream_position = reader.stream_position()?;
-    reader.seek(SeekFrom::End(-22))?;
-    let value = Self::read(reader)?;
+    let end_of_central_directory_position = EndOfCentralDirectory::find(reader)?;
+    reader.seek(SeekFrom::Start(end_of_central_directory_position))?;
+    let value = Self::read_with_options(reader, options)?;
     reader.seek(SeekFrom::Start(initial_stream_position))?;
     Ok(value)
   }
 
   pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
     reader.trace(|reader| {
-      let end = EndOfCentralDirectory::read(reader)?;
+      let end_of_central_directory_position = reader.stream_position()?;
+      let end = EndOfCentralDirectory::read_with_options(reader, options)?;
+
+      let (zip64_end, end_of_central_directory_records_position) =
+        match end_of_central_directory_position.checked_sub(Zip64EndOfCentralDirectoryLocator::default().expected_size()) {
+          Some(locator_position) => {
+            reader.seek(SeekFrom::Start(locator_position))?;
+            let mut signature = [0u8; 4];
+            reader.read_exact(&mut signature)?;
+            if signature == Zip64EndOfCentralDirectoryLocator::SIGNATURE {
+              reader.seek(SeekFrom::Start(locator_position))?;
+              let locator = Zip64EndOfCentralDirectoryLocator::read(reader)?;
+              let zip64_end_position = Self::find_zip64_end(reader, &locator, locator_position)?;
+              reader.seek(SeekFrom::Start(zip64_end_position))?;
+              (Some(Zip64EndOfCentralDirectory::read(reader)?), zip64_end_position)
+            } else {
+              (None, end_of_central_directory_position)
+            }
+          }
+          None => (None, end_of_central_directory_position),
+        };
+
+      let (total_number_of_central_directory_records, size_of_central_directory, offset_of_start_of_central_directory) = match &zip64_end {
+        Some(zip64_end) => (
+          zip64_end.total_number_of_central_directory_records,
+          zip64_end.size_of_central_directory,
+          zip64_end.offset_of_start_of_central_directory_relative_to_start_of_archive,
+        ),
+        None => (
+          end.total_number_of_central_directory_records.into(),
+          end.size_of_central_directory.into(),
+          end.offset_of_start_of_central_directory_relative_to_start_of_archive.into(),
+        ),
+      };
+
+      let prefix_length = end_of_central_directory_records_position
+        .checked_sub(size_of_central_directory)
+        .and_then(|start_of_central_directory| start_of_central_directory.checked_sub(offset_of_start_of_central_directory))
+        .unwrap_or(0);
+
+      #[cfg(feature = "logging")]
+      if prefix_length > 0 {
+        log::debug!("Central Directory offsets are relative to {prefix_length} bytes of prepended data");
+      }
 
-      reader.seek(SeekFrom::Start(end.offset_of_start_of_central_directory_relative_to_start_of_archive.into()))?;
+      reader.seek(SeekFrom::Start(offset_of_start_of_central_directory + prefix_length))?;
 
       let mut files = Vec::new();
-      for _ in 0..end.total_number_of_central_directory_records {
-        let file = CentralDirectoryFileHeader::read(reader)?;
+      for _ in 0..total_number_of_central_directory_records {
+        let file = CentralDirectoryFileHeader::read_with_options(reader, options)?;
         files.push(file);
       }
 
-      let value = Self { files, end };
+      let value = Self {
+        files,
+        zip64_end,
+        end,
+        prefix_length,
+      };
 
       Ok(value)
     })
@@ -71,15 +217,53 @@ impl CentralDirectory {
 
   pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
     writer.trace(self.expected_size(), |writer| {
-      let Self { files, end } = self;
+      let Self {
+        files,
+        zip64_end,
+        end,
+        prefix_length: _,
+      } = self;
 
       for file in files {
         file.write(writer)?;
       }
 
+      if let Some(zip64_end) = zip64_end {
+        let locator = Zip64EndOfCentralDirectoryLocator {
+          offset_of_zip64_end_of_central_directory_record: zip64_end.offset_of_start_of_central_directory_relative_to_start_of_archive
+            + zip64_end.size_of_central_directory,
+          ..Default::default()
+        };
+
+        zip64_end.write(writer)?;
+        locator.write(writer)?;
+      }
+
       end.write(writer)?;
 
       Ok(())
     })
   }
+
+  pub fn write_with_offsets<W: Write + Seek>(&self, writer: &mut W, offsets: Offsets) -> Result<()> {
+    match offsets {
+      Offsets::Relative => self.write(writer),
+      Offsets::Absolute => self.to_absolute()?.write(writer),
+    }
+  }
+
+  fn find_zip64_end<R: Read + Seek>(reader: &mut R, locator: &Zip64EndOfCentralDirectoryLocator, locator_position: u64) -> Result<u64> {
+    let recorded_position = locator.offset_of_zip64_end_of_central_directory_record;
+    let adjacent_position = locator_position.saturating_sub(Zip64EndOfCentralDirectory::default().expected_size());
+
+    for position in [recorded_position, adjacent_position] {
+      reader.seek(SeekFrom::Start(position))?;
+      let mut signature = [0u8; 4];
+      if reader.read_exact(&mut signature).is_ok() && signature == Zip64EndOfCentralDirectory::SIGNATURE {
+        return Ok(position);
+      }
+    }
+
+    Err(Error::BadSignatureInZip64EndOfCentralDirectoryRecord)
+  }
 }
@@ -1,4 +1,5 @@
-use super::LocalFileHeader;
+use super::{CompressionMethod, FileNameEncoding, LocalFileHeader, ParseOptions, UnicodeCommentExtraField, UnicodePathExtraField, Zip64ExtendedInformation};
+use crate::extra_field;
 use crate::prelude::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -20,19 +21,49 @@ pub struct CentralDirectoryFileHeader {
   pub internal_file_attributes: u16,
   pub external_file_attributes: u32,
   pub relative_offset_of_local_file_header: u32,
-  pub file_name: String,
+  pub file_name: Vec<u8>,
   pub extra_field: Vec<u8>,
   pub file_comment: Vec<u8>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CentralDirectoryFileHeaderRef<'a> {
+  pub signature: [u8; 4],
+  pub version_made_by: u16,
+  pub version_needed_to_extract: u16,
+  pub general_purpose_flags: u16,
+  pub compression_method: u16,
+  pub file_last_modification_time: u16,
+  pub file_last_modification_date: u16,
+  pub crc32_of_uncompressed_data: u32,
+  pub compressed_size: u32,
+  pub uncompressed_size: u32,
+  pub file_name_length: u16,
+  pub extra_field_length: u16,
+  pub file_comment_length: u16,
+  pub disk_number_where_file_starts: u16,
+  pub internal_file_attributes: u16,
+  pub external_file_attributes: u32,
+  pub relative_offset_of_local_file_header: u32,
+  pub file_name: &'a [u8],
+  pub extra_field: &'a [u8],
+  pub file_comment: &'a [u8],
+}
+
 impl ExpectedSize for CentralDirectoryFileHeader {
-  fn expected_size(&self) -> u32 {
-    46 + u32::from(self.file_name_length) + u32::from(self.extra_field_length) + u32::from(self.file_comment_length)
+  fn expected_size(&self) -> u64 {
+    46 + u64::from(self.file_name_length) + u64::from(self.extra_field_length) + u64::from(self.file_comment_length)
+  }
+}
+
+impl ExpectedSize for CentralDirectoryFileHeaderRef<'_> {
+  fn expected_size(&self) -> u64 {
+    46 + u64::from(self.file_name_length) + u64::from(self.extra_field_length) + u64::from(self.file_comment_length)
   }
 }
 
 impl CentralDirectoryFileHeader {
-  const SIGNATURE: [u8; 4] = [0x50, 0x4B, 0x01, 0x02];
+  pub const SIGNATURE: [u8; 4] = [0x50, 0x4B, 0x01, 0x02];
 
   pub fn has_valid_signature(&self) -> bool {
     self.signature == Self::SIGNATURE
@@ -42,51 +73,208 @@ impl CentralDirectoryFileHeader {
     crc32fast::hash(uncompressed) == self.crc32_of_uncompressed_data
   }
 
+  pub fn indicates_utf8(&self) -> bool {
+    self.general_purpose_flags & 0b0000100000000000 != 0
+  }
+
+  pub fn decoded_file_name(&self) -> String {
+    self.decoded_file_name_with(FileNameEncoding::default())
+  }
+
+  pub fn decoded_file_name_with(&self, encoding: FileNameEncoding) -> String {
+    if self.indicates_utf8() {
+      FileNameEncoding::Utf8.decode(&self.file_name)
+    } else {
+      encoding.decode(&self.file_name)
+    }
+  }
+
+  pub fn unicode_path(&self) -> Option<UnicodePathExtraField> {
+    extra_field::find(&self.extra_field, UnicodePathExtraField::HEADER_ID).and_then(UnicodePathExtraField::parse)
+  }
+
+  pub fn effective_file_name(&self) -> String {
+    self.effective_file_name_with(FileNameEncoding::default())
+  }
+
+  pub fn effective_file_name_with(&self, encoding: FileNameEncoding) -> String {
+    match self.unicode_path() {
+      Some(unicode_path) if unicode_path.applies_to(&self.file_name) => String::from_utf8_lossy(&unicode_path.unicode_name).into_owned(),
+      _ => self.decoded_file_name_with(encoding),
+    }
+  }
+
+  pub fn unicode_comment(&self) -> Option<UnicodeCommentExtraField> {
+    extra_field::find(&self.extra_field, UnicodeCommentExtraField::HEADER_ID).and_then(UnicodeCommentExtraField::parse)
+  }
+
+  pub fn effective_file_comment(&self) -> String {
+    self.effective_file_comment_with(FileNameEncoding::default())
+  }
+
+  pub fn effective_file_comment_with(&self, encoding: FileNameEncoding) -> String {
+    match self.unicode_comment() {
+      Some(unicode_comment) if unicode_comment.applies_to(&self.file_comment) => String::from_utf8_lossy(&unicode_comment.unicode_comment).into_owned(),
+      _ if self.indicates_utf8() => FileNameEncoding::Utf8.decode(&self.file_comment),
+      _ => encoding.decode(&self.file_comment),
+    }
+  }
+
+  pub fn zip64_extended_information(&self) -> Option<Zip64ExtendedInformation> {
+    extra_field::find(&self.extra_field, Zip64ExtendedInformation::HEADER_ID).map(|data| {
+      Zip64ExtendedInformation::parse(
+        data,
+        self.uncompressed_size == u32::MAX,
+        self.compressed_size == u32::MAX,
+        self.relative_offset_of_local_file_header == u32::MAX,
+        self.disk_number_where_file_starts == u16::MAX,
+      )
+    })
+  }
+
+  pub fn effective_compressed_size(&self) -> u64 {
+    self
+      .zip64_extended_information()
+      .and_then(|zip64_extended_information| zip64_extended_information.compressed_size)
+      .unwrap_or(self.compressed_size.into())
+  }
+
+  pub fn effective_uncompressed_size(&self) -> u64 {
+    self
+      .zip64_extended_information()
+      .and_then(|zip64_extended_information| zip64_extended_information.uncompressed_size)
+      .unwrap_or(self.uncompressed_size.into())
+  }
+
+  pub fn effective_relative_offset_of_local_file_header(&self) -> u64 {
+    self
+      .zip64_extended_information()
+      .and_then(|zip64_extended_information| zip64_extended_information.relative_offset_of_local_file_header)
+      .unwrap_or(self.relative_offset_of_local_file_header.into())
+  }
+
+  pub fn set_sizes_and_offset(&mut self, compressed_size: u64, uncompressed_size: u64, relative_offset_of_local_file_header: u64) -> Result<()> {
+    let requires_zip64 = |value: u64| (value >= u32::MAX.into()).then_some(value);
+
+    let zip64_extended_information = Zip64ExtendedInformation {
+      uncompressed_size: requires_zip64(uncompressed_size),
+      compressed_size: requires_zip64(compressed_size),
+      relative_offset_of_local_file_header: requires_zip64(relative_offset_of_local_file_header),
+      disk_number_where_file_starts: self.zip64_extended_information().and_then(|existing| existing.disk_number_where_file_starts),
+    };
+
+    self.compressed_size = compressed_size.try_into().unwrap_or(u32::MAX);
+    self.uncompressed_size = uncompressed_size.try_into().unwrap_or(u32::MAX);
+    self.relative_offset_of_local_file_header = relative_offset_of_local_file_header.try_into().unwrap_or(u32::MAX);
+
+    if zip64_extended_information.is_empty() {
+      self.set_extra_field(extra_field::replace(&self.extra_field, Zip64ExtendedInformation::HEADER_ID, None)?)
+    } else {
+      self.version_needed_to_extract = self.version_needed_to_extract.max(Zip64ExtendedInformation::VERSION_NEEDED_TO_EXTRACT);
+      self.set_extra_field(extra_field::replace(
+        &self.extra_field,
+        Zip64ExtendedInformation::HEADER_ID,
+        Some(&zip64_extended_information.to_bytes()?),
+      )?)
+    }
+  }
+
+  pub fn set_relative_offset_of_local_file_header(&mut self, relative_offset_of_local_file_header: u64) -> Result<()> {
+    self.set_sizes_and_offset(
+      self.effective_compressed_size(),
+      self.effective_uncompressed_size(),
+      relative_offset_of_local_file_header,
+    )
+  }
+
+  pub fn inherit_metadata_from(&mut self, other: &Self) -> Result<()> {
+    self.version_made_by = (other.version_made_by & 0xFF00) | (other.version_made_by & 0x00FF).max(self.version_made_by & 0x00FF);
+    self.internal_file_attributes = other.internal_file_attributes;
+    self.external_file_attributes = other.external_file_attributes;
+    self.file_comment_length = other.file_comment_length;
+    self.file_comment = other.file_comment.clone();
+
+    let zip64_extended_information = extra_field::find(&self.extra_field, Zip64ExtendedInformation::HEADER_ID).map(<[u8]>::to_vec);
+    let extra_field = extra_field::replace(&other.extra_field, Zip64ExtendedInformation::HEADER_ID, zip64_extended_information.as_deref())?;
+    self.set_extra_field(extra_field)
+  }
+
+  pub fn transcode_to_utf8(&mut self, encoding: FileNameEncoding) -> Result<()> {
+    if self.indicates_utf8() {
+      return Ok(());
+    }
+
+    let file_name = self.effective_file_name_with(encoding).into_bytes();
+    let file_comment = self.effective_file_comment_with(encoding).into_bytes();
+    self.set_file_name(file_name)?;
+    self.set_file_comment(file_comment)?;
+
+    let extra_field = extra_field::replace(&self.extra_field, UnicodePathExtraField::HEADER_ID, None)?;
+    self.set_extra_field(extra_field::replace(&extra_field, UnicodeCommentExtraField::HEADER_ID, None)?)?;
+    self.general_purpose_flags |= 0b0000100000000000;
+    Ok(())
+  }
+
+  pub fn set_file_name(&mut self, file_name: Vec<u8>) -> Result<()> {
+    self.file_name_length = file_name.len().try_into()?;
+    self.file_name = file_name;
+    Ok(())
+  }
+
+  pub fn set_file_comment(&mut self, file_comment: Vec<u8>) -> Result<()> {
+    self.file_comment_length = file_comment.len().try_into()?;
+    self.file_comment = file_comment;
+    Ok(())
+  }
+
+  fn set_extra_field(&mut self, extra_field: Vec<u8>) -> Result<()> {
+    self.extra_field_length = extra_field.len().try_into()?;
+    self.extra_field = extra_field;
+    Ok(())
+  }
+
   pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
     reader.trace(|reader| {
+      let offset = reader.stream_position()?;
+      let mut fixed = [0u8; 46];
+      reader.read_exact(&mut fixed)?;
+      let mut fields = fixed.as_slice();
+
       let mut signature = [0u8; 4];
-      reader.read_exact(&mut signature)?;
+      fields.read_exact(&mut signature)?;
 
       if signature != Self::SIGNATURE {
         #[cfg(feature = "logging")]
         log::error!("read signature={signature:?} != {:?}", Self::SIGNATURE);
-        return Err(Error::BadSignatureInCentralDirectoryFileHeader);
+        options.recover(offset, Error::BadSignatureInCentralDirectoryFileHeader)?;
       }
 
-      let version_made_by = reader.read_u16::<LittleEndian>()?;
-      let version_needed_to_extract = reader.read_u16::<LittleEndian>()?;
-      let general_purpose_flags = reader.read_u16::<LittleEndian>()?;
-      let compression_method = reader.read_u16::<LittleEndian>()?;
-      let file_last_modification_time = reader.read_u16::<LittleEndian>()?;
-      let file_last_modification_date = reader.read_u16::<LittleEndian>()?;
-      let crc32_of_uncompressed_data = reader.read_u32::<LittleEndian>()?;
-      let compressed_size = reader.read_u32::<LittleEndian>()?;
-      let uncompressed_size = reader.read_u32::<LittleEndian>()?;
-      let file_name_length = reader.read_u16::<LittleEndian>()?;
-      let extra_field_length = reader.read_u16::<LittleEndian>()?;
-      let file_comment_length = reader.read_u16::<LittleEndian>()?;
-      let disk_number_where_file_starts = reader.read_u16::<LittleEndian>()?;
-      let internal_file_attributes = reader.read_u16::<LittleEndian>()?;
-      let external_file_attributes = reader.read_u32::<LittleEndian>()?;
-      let relative_offset_of_local_file_header = reader.read_u32::<LittleEndian>()?;
-
-      let file_name = {
-        let mut file_name = Vec::with_capacity(file_name_length.into());
-        for _ in 0..file_name_length {
-          file_name.push(reader.read_u8()?);
-        }
-        String::from_utf8(file_name)?
-      };
-
-      let mut extra_field = Vec::with_capacity(extra_field_length.into());
-      for _ in 0..extra_field_length {
-        extra_field.push(reader.read_u8()?);
-      }
+      let version_made_by = fields.read_u16::<LittleEndian>()?;
+      let version_needed_to_extract = fields.read_u16::<LittleEndian>()?;
+      let general_purpose_flags = fields.read_u16::<LittleEndian>()?;
+      let compression_method = fields.read_u16::<LittleEndian>()?;
+      let file_last_modification_time = fields.read_u16::<LittleEndian>()?;
+      let file_last_modification_date = fields.read_u16::<LittleEndian>()?;
+      let crc32_of_uncompressed_data = fields.read_u32::<LittleEndian>()?;
+      let compressed_size = fields.read_u32::<LittleEndian>()?;
+      let uncompressed_size = fields.read_u32::<LittleEndian>()?;
+      let file_name_length = fields.read_u16::<LittleEndian>()?;
+      let extra_field_length = fields.read_u16::<LittleEndian>()?;
+      let file_comment_length = fields.read_u16::<LittleEndian>()?;
+      let disk_number_where_file_starts = fields.read_u16::<LittleEndian>()?;
+      let internal_file_attributes = fields.read_u16::<LittleEndian>()?;
+      let external_file_attributes = fields.read_u32::<LittleEndian>()?;
+      let relative_offset_of_local_file_header = fields.read_u32::<LittleEndian>()?;
 
-      let mut file_comment = Vec::with_capacity(file_comment_length.into());
-      for _ in 0..file_comment_length {
-        file_comment.push(reader.read_u8()?);
-      }
+      let mut file_name = reader.read_vec(usize::from(file_name_length) + usize::from(extra_field_length) + usize::from(file_comment_length))?;
+      let mut extra_field = file_name.split_off(file_name_length.into());
+      let file_comment = extra_field.split_off(extra_field_length.into());
+      options.check_file_name(offset, &file_name, &extra_field, general_purpose_flags)?;
+      options.check_file_comment(offset, &file_comment, &extra_field);
 
       let value = Self {
         signature,
@@ -161,7 +349,7 @@ impl CentralDirectoryFileHeader {
       writer.write_u16::<LittleEndian>(*internal_file_attributes)?;
       writer.write_u32::<LittleEndian>(*external_file_attributes)?;
       writer.write_u32::<LittleEndian>(*relative_offset_of_local_file_header)?;
-      writer.write_all(file_name.as_bytes())?;
+      writer.write_all(file_name)?;
       writer.write_all(extra_field)?;
       writer.write_all(file_comment)?;
 
@@ -188,6 +376,8 @@ impl From<LocalFileHeader> for CentralDirectoryFileHeader {
       extra_field,
     } = local_file_header;
 
+    let version_needed_to_extract = version_needed_to_extract.max(CompressionMethod::from(compression_method).version_needed_to_extract());
+
     Self {
       signature: Self::SIGNATURE,
       version_made_by: version_needed_to_extract,
@@ -212,3 +402,117 @@ impl From<LocalFileHeader> for CentralDirectoryFileHeader {
     }
   }
 }
+
+impl<'a> CentralDirectoryFileHeaderRef<'a> {
+  pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+    let mut fields = bytes.get(..46).ok_or_else(Error::unexpected_eof)?;
+
+    let mut signature = [0u8; 4];
+    fields.read_exact(&mut signature)?;
+
+    if signature != CentralDirectoryFileHeader::SIGNATURE {
+      #[cfg(feature = "logging")]
+      log::error!("parse signature={signature:?} != {:?}", CentralDirectoryFileHeader::SIGNATURE);
+      return Err(Error::BadSignatureInCentralDirectoryFileHeader);
+    }
+
+    let version_made_by = fields.read_u16::<LittleEndian>()?;
+    let version_needed_to_extract = fields.read_u16::<LittleEndian>()?;
+    let general_purpose_flags = fields.read_u16::<LittleEndian>()?;
+    let compression_method = fields.read_u16::<LittleEndian>()?;
+    let file_last_modification_time = fields.read_u16::<LittleEndian>()?;
+    let file_last_modification_date = fields.read_u16::<LittleEndian>()?;
+    let crc32_of_uncompressed_data = fields.read_u32::<LittleEndian>()?;
+    let compressed_size = fields.read_u32::<LittleEndian>()?;
+    let uncompressed_size = fields.read_u32::<LittleEndian>()?;
+    let file_name_length = fields.read_u16::<LittleEndian>()?;
+    let extra_field_length = fields.read_u16::<LittleEndian>()?;
+    let file_comment_length = fields.read_u16::<LittleEndian>()?;
+    let disk_number_where_file_starts = fields.read_u16::<LittleEndian>()?;
+    let internal_file_attributes = fields.read_u16::<LittleEndian>()?;
+    let external_file_attributes = fields.read_u32::<LittleEndian>()?;
+    let relative_offset_of_local_file_header = fields.read_u32::<LittleEndian>()?;
+
+    let end_of_file_name = 46 + usize::from(file_name_length);
+    let end_of_extra_field = end_of_file_name + usize::from(extra_field_length);
+    let end_of_file_comment = end_of_extra_field + usize::from(file_comment_length);
+    let file_name = bytes.get(46..end_of_file_name).ok_or_else(Error::unexpected_eof)?;
+    let extra_field = bytes.get(end_of_file_name..end_of_extra_field).ok_or_else(Error::unexpected_eof)?;
+    let file_comment = bytes.get(end_of_extra_field..end_of_file_comment).ok_or_else(Error::unexpected_eof)?;
+
+    let value = Self {
+      signature,
+      version_made_by,
+      version_needed_to_extract,
+      general_purpose_flags,
+      compression_method,
+      file_last_modification_time,
+      file_last_modification_date,
+      crc32_of_uncompressed_data,
+      compressed_size,
+      uncompressed_size,
+      file_name_length,
+      extra_field_length,
+      file_comment_length,
+      disk_number_where_file_starts,
+      internal_file_attributes,
+      external_file_attributes,
+      relative_offset_of_local_file_header,
+      file_name,
+      extra_field,
+      file_comment,
+    };
+
+    Ok(value)
+  }
+}
+
+impl From<CentralDirectoryFileHeaderRef<'_>> for CentralDirectoryFileHeader {
+  fn from(central_directory_file_header: CentralDirectoryFileHeaderRef<'_>) -> Self {
+    let CentralDirectoryFileHeaderRef {
+      signature,
+      version_made_by,
+      version_needed_to_extract,
+      general_purpose_flags,
+      compression_method,
+      file_last_modification_time,
+      file_last_modification_date,
+      crc32_of_uncompressed_data,
+      compressed_size,
+      uncompressed_size,
+      file_name_length,
+      extra_field_length,
+      file_comment_length,
+      disk_number_where_file_starts,
+      internal_file_attributes,
+      external_file_attributes,
+      relative_offset_of_local_file_header,
+      file_name,
+      extra_field,
+      file_comment,
+    } = central_directory_file_header;
+
+    Self {
+      signature,
+      version_made_by,
+      version_needed_to_extract,
+      general_purpose_flags,
+      compression_method,
+      file_last_modification_time,
+      file_last_modification_date,
+      crc32_of_uncompressed_data,
+      compressed_size,
+      uncompressed_size,
+      file_name_length,
+      extra_field_length,
+      file_comment_length,
+      disk_number_where_file_starts,
+      internal_file_attributes,
+      external_file_attributes,
+      relative_offset_of_local_file_header,
+      file_name: file_name.to_vec(),
+      extra_field: extra_field.to_vec(),
+      file_comment: file_comment.to_vec(),
+    }
+  }
+}
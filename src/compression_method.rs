@@ -0,0 +1,158 @@
+use crate::lzma::LzmaDecoder;
+use crate::prelude::*;
+use crate::{implode, reduce, shrink, LocalFileHeader};
+use bzip2::read::BzDecoder;
+use bzip2::write::BzEncoder;
+use deflate64::Deflate64Decoder;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use std::io::Cursor;
+#[cfg(feature = "xz")]
+use xz2::{read::XzDecoder, write::XzEncoder};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CompressionMethod {
+  Stored,
+  Shrunk,
+  Reduced(u16),
+  Imploded,
+  Deflated,
+  Deflate64,
+  Bzip2,
+  Lzma,
+  Zstd,
+  Xz,
+  Other(u16),
+}
+
+impl From<u16> for CompressionMethod {
+  fn from(compression_method: u16) -> Self {
+    match compression_method {
+      0 => Self::Stored,
+      1 => Self::Shrunk,
+      2..=5 => Self::Reduced(compression_method - 1),
+      6 => Self::Imploded,
+      8 => Self::Deflated,
+      9 => Self::Deflate64,
+      12 => Self::Bzip2,
+      14 => Self::Lzma,
+      93 => Self::Zstd,
+      95 => Self::Xz,
+      other => Self::Other(other),
+    }
+  }
+}
+
+impl From<CompressionMethod> for u16 {
+  fn from(compression_method: CompressionMethod) -> Self {
+    match compression_method {
+      CompressionMethod::Stored => 0,
+      CompressionMethod::Shrunk => 1,
+      CompressionMethod::Reduced(compression_factor) => compression_factor + 1,
+      CompressionMethod::Imploded => 6,
+      CompressionMethod::Deflated => 8,
+      CompressionMethod::Deflate64 => 9,
+      CompressionMethod::Bzip2 => 12,
+      CompressionMethod::Lzma => 14,
+      CompressionMethod::Zstd => 93,
+      CompressionMethod::Xz => 95,
+      CompressionMethod::Other(other) => other,
+    }
+  }
+}
+
+impl CompressionMethod {
+  pub fn is_supported(&self) -> bool {
+    match self {
+      Self::Zstd => cfg!(feature = "zstd"),
+      Self::Xz => cfg!(feature = "xz"),
+      other => !matches!(other, Self::Other(_)),
+    }
+  }
+
+  pub fn version_needed_to_extract(&self) -> u16 {
+    match self {
+      Self::Stored | Self::Shrunk | Self::Reduced(_) | Self::Imploded | Self::Other(_) => 10,
+      Self::Deflated => 20,
+      Self::Deflate64 => 21,
+      Self::Bzip2 => 46,
+      Self::Lzma | Self::Zstd | Self::Xz => 63,
+    }
+  }
+
+  pub fn decoder<'a, R: Read + 'a>(&self, reader: R) -> Result<Box<dyn Read + 'a>> {
+    self.decoder_for(reader, 0, false, None)
+  }
+
+  pub fn decoder_with<'a, R: Read + 'a>(&self, reader: R, header: &LocalFileHeader, uncompressed_size: Option<u64>) -> Result<Box<dyn Read + 'a>> {
+    self.decoder_for(reader, header.general_purpose_flags, header.indicates_lzma_end_of_stream_marker(), uncompressed_size)
+  }
+
+  fn decoder_for<'a, R: Read + 'a>(
+    &self,
+    mut reader: R,
+    general_purpose_flags: u16,
+    lzma_end_of_stream_marker: bool,
+    uncompressed_size: Option<u64>,
+  ) -> Result<Box<dyn Read + 'a>> {
+    let mut read_fully = || -> Result<(Vec<u8>, Option<usize>)> {
+      let mut compressed = Vec::new();
+      reader.read_to_end(&mut compressed)?;
+      Ok((compressed, uncompressed_size.map(usize::try_from).transpose()?))
+    };
+
+    match self {
+      Self::Stored => Ok(Box::new(reader)),
+      Self::Shrunk => {
+        let (compressed, uncompressed_size) = read_fully()?;
+        Ok(Box::new(Cursor::new(shrink::unshrink(&compressed, uncompressed_size)?)))
+      }
+      // Reduced and Imploded data has no end code, so without the size the padding bits would decode as extra output.
+      Self::Reduced(compression_factor) => {
+        let (compressed, uncompressed_size) = read_fully()?;
+        let uncompressed_size = uncompressed_size.ok_or(Error::MissingUncompressedSize((*self).into()))?;
+        Ok(Box::new(Cursor::new(reduce::unreduce(&compressed, *compression_factor, uncompressed_size)?)))
+      }
+      Self::Imploded => {
+        let (compressed, uncompressed_size) = read_fully()?;
+        let uncompressed_size = uncompressed_size.ok_or(Error::MissingUncompressedSize((*self).into()))?;
+        Ok(Box::new(Cursor::new(implode::explode(&compressed, general_purpose_flags, uncompressed_size)?)))
+      }
+      Self::Deflated => Ok(Box::new(DeflateDecoder::new(reader))),
+      Self::Deflate64 => Ok(Box::new(Deflate64Decoder::new(reader))),
+      Self::Bzip2 => Ok(Box::new(BzDecoder::new(reader))),
+      Self::Lzma if lzma_end_of_stream_marker => Ok(Box::new(LzmaDecoder::new(reader, None)?)),
+      Self::Lzma => Ok(Box::new(LzmaDecoder::new(reader, uncompressed_size)?)),
+      #[cfg(feature = "zstd")]
+      Self::Zstd => Ok(Box::new(zstd::stream::read::Decoder::new(reader)?)),
+      #[cfg(feature = "xz")]
+      Self::Xz => Ok(Box::new(XzDecoder::new(reader))),
+      _ => Err(Error::UnsupportedCompressionMethod((*self).into())),
+    }
+  }
+
+  pub fn compress(&self, uncompressed: &[u8]) -> Result<Vec<u8>> {
+    match self {
+      Self::Stored => Ok(uncompressed.to_vec()),
+      Self::Deflated => {
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(uncompressed)?;
+        Ok(encoder.finish()?)
+      }
+      Self::Bzip2 => {
+        let mut encoder = BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        encoder.write_all(uncompressed)?;
+        Ok(encoder.finish()?)
+      }
+      #[cfg(feature = "zstd")]
+      Self::Zstd => Ok(zstd::stream::encode_all(uncompressed, 0)?),
+      #[cfg(feature = "xz")]
+      Self::Xz => {
+        let mut encoder = XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(uncompressed)?;
+        Ok(encoder.finish()?)
+      }
+      _ => Err(Error::UnsupportedCompressionMethod((*self).into())),
+    }
+  }
+}
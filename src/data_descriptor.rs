@@ -1,3 +1,4 @@
+use super::{CentralDirectoryFileHeader, EndOfCentralDirectory, LocalFileHeader, ParseOptions, Zip64EndOfCentralDirectory};
 use crate::prelude::*;
 use std::io::SeekFrom;
 
@@ -5,21 +6,23 @@ use std::io::SeekFrom;
 pub struct DataDescriptor {
   pub signature: Option<[u8; 4]>,
   pub crc32_of_uncompressed_data: u32,
-  pub compressed_size: u32,
-  pub uncompressed_size: u32,
+  pub compressed_size: u64,
+  pub uncompressed_size: u64,
+  pub zip64: bool,
 }
 
 impl ExpectedSize for DataDescriptor {
-  fn expected_size(&self) -> u32 {
+  fn expected_size(&self) -> u64 {
+    let sizes = if self.zip64 { 16 } else { 8 };
     match self.signature {
-      Some(_) => 16,
-      None => 12,
+      Some(_) => 8 + sizes,
+      None => 4 + sizes,
     }
   }
 }
 
 impl DataDescriptor {
-  const SIGNATURE: [u8; 4] = [0x50, 0x4B, 0x07, 0x08];
+  pub const SIGNATURE: [u8; 4] = [0x50, 0x4B, 0x07, 0x08];
 
   pub fn read_from_end_with_signature<R: Read + Seek>(reader: &mut R) -> Result<Self> {
     let initial_stream_position = reader.stream_position()?;
@@ -47,28 +50,108 @@ impl DataDescriptor {
   }
 
   pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
+    Self::read_with_size_fields(reader, false, options)
+  }
+
+  pub fn read_zip64<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_zip64_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_zip64_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
+    Self::read_with_size_fields(reader, true, options)
+  }
+
+  pub(crate) fn read_after<R: Read + Seek>(reader: &mut R, zip64: bool, compressed_size: u64) -> Result<Self> {
+    Self::read_after_with_options(reader, zip64, compressed_size, &mut ParseOptions::default())
+  }
+
+  pub(crate) fn read_after_with_options<R: Read + Seek>(reader: &mut R, zip64: bool, compressed_size: u64, options: &mut ParseOptions) -> Result<Self> {
+    let offset = reader.stream_position()?;
+    let mut data_descriptor = Self::read_with_size_fields(reader, zip64, options)?;
+
+    // The descriptor has been consumed either way, so keep it with the size of the data that actually precedes it.
+    if data_descriptor.compressed_size != compressed_size {
+      options.recover(offset, Error::DataDescriptorConflictsWithCompressedData)?;
+      data_descriptor.compressed_size = compressed_size;
+    }
+
+    Ok(data_descriptor)
+  }
+
+  pub(crate) fn try_read_after<R: Read + Seek>(reader: &mut R, zip64: bool, compressed_size: u64, options: &mut ParseOptions) -> Result<Option<Self>> {
+    let initial_stream_position = reader.stream_position()?;
+
+    let result = if Self::is_followed_by_another_record(reader)? {
+      Err(Error::MissingDataDescriptor)
+    } else {
+      Self::read_after_with_options(reader, zip64, compressed_size, options)
+    };
+
+    match result {
+      Ok(data_descriptor) => Ok(Some(data_descriptor)),
+      Err(Error::Io(error)) if error.kind() != std::io::ErrorKind::UnexpectedEof => Err(error.into()),
+      Err(Error::Io(_) | Error::MissingDataDescriptor) => {
+        reader.seek(SeekFrom::Start(initial_stream_position))?;
+        options.warn(initial_stream_position, Error::MissingDataDescriptor);
+        Ok(None)
+      }
+      Err(error) => Err(error),
+    }
+  }
+
+  fn is_followed_by_another_record<R: Read + Seek>(reader: &mut R) -> Result<bool> {
+    let initial_stream_position = reader.stream_position()?;
+    let mut signature = [0u8; 4];
+    let result = reader.read_exact(&mut signature);
+    reader.seek(SeekFrom::Start(initial_stream_position))?;
+
+    match result {
+      Ok(()) => Ok(
+        [
+          LocalFileHeader::SIGNATURE,
+          CentralDirectoryFileHeader::SIGNATURE,
+          EndOfCentralDirectory::SIGNATURE,
+          Zip64EndOfCentralDirectory::SIGNATURE,
+        ]
+        .contains(&signature),
+      ),
+      Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+      Err(error) => Err(error.into()),
+    }
+  }
+
+  fn read_with_size_fields<R: Read + Seek>(reader: &mut R, zip64: bool, options: &mut ParseOptions) -> Result<Self> {
     reader.trace(|reader| {
-      let signature = {
+      let offset = reader.stream_position()?;
+      let (signature, crc32_of_uncompressed_data) = {
         let mut signature = [0u8; 4];
 
         reader.read_exact(&mut signature)?;
 
         if signature == Self::SIGNATURE {
-          Some(signature)
+          (Some(signature), reader.read_u32::<LittleEndian>()?)
         } else {
-          None
+          options.warn(offset, Error::MissingSignatureInDataDescriptor);
+          (None, u32::from_le_bytes(signature))
         }
       };
 
-      let crc32_of_uncompressed_data = reader.read_u32::<LittleEndian>()?;
-      let compressed_size = reader.read_u32::<LittleEndian>()?;
-      let uncompressed_size = reader.read_u32::<LittleEndian>()?;
+      let (compressed_size, uncompressed_size) = if zip64 {
+        (reader.read_u64::<LittleEndian>()?, reader.read_u64::<LittleEndian>()?)
+      } else {
+        (reader.read_u32::<LittleEndian>()?.into(), reader.read_u32::<LittleEndian>()?.into())
+      };
 
       let value = Self {
         signature,
         crc32_of_uncompressed_data,
         compressed_size,
         uncompressed_size,
+        zip64,
       };
 
       Ok(value)
@@ -82,6 +165,7 @@ impl DataDescriptor {
         crc32_of_uncompressed_data: crc32,
         compressed_size,
         uncompressed_size,
+        zip64,
       } = self;
 
       if let Some(signature) = signature {
@@ -89,8 +173,13 @@ impl DataDescriptor {
       };
 
       writer.write_u32::<LittleEndian>(*crc32)?;
-      writer.write_u32::<LittleEndian>(*compressed_size)?;
-      writer.write_u32::<LittleEndian>(*uncompressed_size)?;
+      if *zip64 {
+        writer.write_u64::<LittleEndian>(*compressed_size)?;
+        writer.write_u64::<LittleEndian>(*uncompressed_size)?;
+      } else {
+        writer.write_u32::<LittleEndian>((*compressed_size).try_into()?)?;
+        writer.write_u32::<LittleEndian>((*uncompressed_size).try_into()?)?;
+      }
 
       Ok(())
     })
@@ -1,4 +1,6 @@
+use super::ParseOptions;
 use crate::prelude::*;
+use std::io::SeekFrom;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct EndOfCentralDirectory {
@@ -30,40 +32,86 @@ impl Default for EndOfCentralDirectory {
 }
 
 impl ExpectedSize for EndOfCentralDirectory {
-  fn expected_size(&self) -> u32 {
-    22 + u32::from(self.comment_length)
+  fn expected_size(&self) -> u64 {
+    22 + u64::from(self.comment_length)
   }
 }
 
 impl EndOfCentralDirectory {
-  const SIGNATURE: [u8; 4] = [0x50, 0x4B, 0x05, 0x06];
+  pub const SIGNATURE: [u8; 4] = [0x50, 0x4B, 0x05, 0x06];
 
   pub fn has_valid_signature(&self) -> bool {
     self.signature == Self::SIGNATURE
   }
 
+  pub fn find<R: Read + Seek>(reader: &mut R) -> Result<u64> {
+    let initial_stream_position = reader.stream_position()?;
+    let length = reader.seek(SeekFrom::End(0))?;
+    let start_of_search = length.saturating_sub(22 + u64::from(u16::MAX));
+    reader.seek(SeekFrom::Start(start_of_search))?;
+    let buffer = reader.read_vec((length - start_of_search).try_into()?)?;
+    reader.seek(SeekFrom::Start(initial_stream_position))?;
+
+    let candidates = || {
+      (0..buffer.len().saturating_sub(21))
+        .rev()
+        .filter(|&index| buffer[index..index + 4] == Self::SIGNATURE)
+    };
+    let remaining_after_comment = |index: usize| {
+      let comment_length = usize::from(u16::from_le_bytes([buffer[index + 20], buffer[index + 21]]));
+      (buffer.len() - index - 22).checked_sub(comment_length)
+    };
+
+    if let Some(index) = candidates().find(|&index| remaining_after_comment(index) == Some(0)) {
+      return Ok(start_of_search + u64::try_from(index)?);
+    }
+
+    if let Some(index) = candidates().find(|&index| remaining_after_comment(index).is_some()) {
+      #[cfg(feature = "logging")]
+      log::warn!(
+        "End of Central Directory comment is followed by {} unexpected bytes",
+        remaining_after_comment(index).unwrap_or_default()
+      );
+      return Ok(start_of_search + u64::try_from(index)?);
+    }
+
+    Err(Error::MissingEndOfCentralDirectory)
+  }
+
   pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
     reader.trace(|reader| {
+      let offset = reader.stream_position()?;
+      let mut fixed = [0u8; 22];
+      reader.read_exact(&mut fixed)?;
+      let mut fields = fixed.as_slice();
+
       let mut signature = [0u8; 4];
-      reader.read_exact(&mut signature)?;
+      fields.read_exact(&mut signature)?;
 
       if signature != Self::SIGNATURE {
         #[cfg(feature = "logging")]
         log::error!("read signature={signature:?} != {:?}", Self::SIGNATURE);
-        return Err(Error::BadSignatureInEndOfCentralDirectoryHeader);
+        options.recover(offset, Error::BadSignatureInEndOfCentralDirectoryHeader)?;
       }
 
-      let number_of_this_disk = reader.read_u16::<LittleEndian>()?;
-      let disk_where_central_directory_starts = reader.read_u16::<LittleEndian>()?;
-      let number_of_central_directory_records_on_this_disk = reader.read_u16::<LittleEndian>()?;
-      let total_number_of_central_directory_records = reader.read_u16::<LittleEndian>()?;
-      let size_of_central_directory = reader.read_u32::<LittleEndian>()?;
-      let offset_of_start_of_central_directory_relative_to_start_of_archive = reader.read_u32::<LittleEndian>()?;
-      let comment_length = reader.read_u16::<LittleEndian>()?;
-
-      let mut comment = Vec::with_capacity(comment_length.into());
-      for _ in 0..comment_length {
-        comment.push(reader.read_u8()?);
+      let number_of_this_disk = fields.read_u16::<LittleEndian>()?;
+      let disk_where_central_directory_starts = fields.read_u16::<LittleEndian>()?;
+      let number_of_central_directory_records_on_this_disk = fields.read_u16::<LittleEndian>()?;
+      let total_number_of_central_directory_records = fields.read_u16::<LittleEndian>()?;
+      let size_of_central_directory = fields.read_u32::<LittleEndian>()?;
+      let offset_of_start_of_central_directory_relative_to_start_of_archive = fields.read_u32::<LittleEndian>()?;
+      let mut comment_length = fields.read_u16::<LittleEndian>()?;
+
+      let mut comment = Vec::new();
+      reader.by_ref().take(comment_length.into()).read_to_end(&mut comment)?;
+      if comment.len() != usize::from(comment_length) {
+        let found = comment.len().try_into()?;
+        options.recover(offset, Error::TruncatedComment { expected: comment_length, found })?;
+        comment_length = found;
       }
 
       let value = Self {
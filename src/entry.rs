@@ -1,61 +1,219 @@
-use super::{DataDescriptor, LocalFileHeader};
+use super::{CentralDirectoryFileHeader, CompressionMethod, DataDescriptor, LocalEntry, LocalFileHeader, LocalFileHeaderRef, ParseOptions, Verification};
+use crate::boundary;
 use crate::prelude::*;
-use flate2::read::DeflateDecoder;
+use crate::search::Search;
+use std::io::{Cursor, SeekFrom};
 
-#[derive(derivative::Derivative, PartialEq, Eq, PartialOrd, Ord, Hash)]
+const MAXIMUM_COMPRESSION_RATIO: u64 = 1032;
+const MAXIMUM_PREALLOCATION: u64 = 64 * 1024 * 1024;
+
+#[derive(derivative::Derivative, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[derivative(Debug)]
 pub struct Entry {
   pub header: LocalFileHeader,
   #[derivative(Debug = "ignore")]
   pub data: Vec<u8>,
   pub data_descriptor: Option<DataDescriptor>,
+  pub incomplete: bool,
+}
+
+#[derive(derivative::Derivative, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derivative(Debug)]
+pub struct EntryRef<'a> {
+  pub header: LocalFileHeaderRef<'a>,
+  #[derivative(Debug = "ignore")]
+  pub data: &'a [u8],
+  pub data_descriptor: Option<DataDescriptor>,
 }
 
 impl ExpectedSize for Entry {
-  fn expected_size(&self) -> u32 {
+  fn expected_size(&self) -> u64 {
+    // The sizes come from the header or Data Descriptor and may be hostile, so saturate instead of overflowing.
+    self.header.expected_size().saturating_add(match self.data_descriptor {
+      Some(data_descriptor) => data_descriptor.compressed_size.saturating_add(data_descriptor.expected_size()),
+      None if self.incomplete => self.data.len() as u64,
+      None => self.header.effective_compressed_size(),
+    })
+  }
+}
+
+impl ExpectedSize for EntryRef<'_> {
+  fn expected_size(&self) -> u64 {
     self.header.expected_size()
+      + self.data.len() as u64
       + match self.data_descriptor {
-        Some(data_descriptor) => data_descriptor.compressed_size + data_descriptor.expected_size(),
-        None => self.header.compressed_size,
+        Some(data_descriptor) => data_descriptor.expected_size(),
+        None => 0,
       }
   }
 }
 
+impl LocalEntry for Entry {
+  fn effective_header(&self) -> Result<LocalFileHeader> {
+    Entry::effective_header(self)
+  }
+
+  fn size_in_archive(&self) -> u64 {
+    self.expected_size()
+  }
+}
+
 impl Entry {
+  pub fn new(file_name: Vec<u8>, uncompressed: &[u8], compression_method: CompressionMethod) -> Result<Self> {
+    let data = compression_method.compress(uncompressed)?;
+    let general_purpose_flags = if !file_name.is_ascii() && std::str::from_utf8(&file_name).is_ok() {
+      0b0000100000000000
+    } else {
+      0
+    };
+
+    let mut header = LocalFileHeader {
+      signature: LocalFileHeader::SIGNATURE,
+      version_needed_to_extract: compression_method.version_needed_to_extract(),
+      general_purpose_flags,
+      compression_method: compression_method.into(),
+      file_last_modification_time: 0,
+      file_last_modification_date: 0b0000000000100001,
+      crc32_of_uncompressed_data: crc32fast::hash(uncompressed),
+      compressed_size: 0,
+      uncompressed_size: 0,
+      file_name_length: file_name.len().try_into()?,
+      extra_field_length: 0,
+      file_name,
+      extra_field: Vec::new(),
+    };
+    header.set_sizes(data.len().try_into()?, uncompressed.len().try_into()?)?;
+
+    let value = Self {
+      header,
+      data,
+      data_descriptor: None,
+      incomplete: false,
+    };
+
+    Ok(value)
+  }
+
   pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
     reader.trace(|reader| {
-      let header = LocalFileHeader::read(reader)?;
+      let offset = reader.stream_position()?;
+      let mut header = LocalFileHeader::read_with_options(reader, options)?;
 
-      let data = if header.indicates_data_descriptor_is_present() {
-        let data_descriptor = DataDescriptor::read_from_end(reader)?;
-        let mut data = Vec::with_capacity(data_descriptor.compressed_size.try_into()?);
-        for _ in 0..data_descriptor.compressed_size {
-          data.push(reader.read_u8()?);
-        }
-        data
+      let compressed_size = if header.indicates_data_descriptor_is_present() {
+        boundary::find_end_of_data(
+          reader,
+          header.compression_method,
+          header.indicates_lzma_end_of_stream_marker(),
+          header.indicates_zip64(),
+        )?
       } else {
-        let mut data = Vec::with_capacity(header.compressed_size.try_into()?);
-        for _ in 0..header.compressed_size {
-          data.push(reader.read_u8()?);
-        }
-        data
+        header.effective_compressed_size()
       };
 
+      let data = reader.read_vec(compressed_size.try_into()?)?;
       let data_descriptor = if header.indicates_data_descriptor_is_present() {
-        Some(DataDescriptor::read(reader)?)
+        let data_descriptor = DataDescriptor::try_read_after(reader, header.indicates_zip64(), compressed_size, options)?;
+        match data_descriptor {
+          Some(data_descriptor) => header.reconcile_with_options(data_descriptor, offset, options)?,
+          None => {
+            let measured = boundary::measure_uncompressed_data(data.as_slice(), &header, compressed_size)?;
+            header.update_without_data_descriptor_with_options(measured, offset, options)?;
+          }
+        }
+        data_descriptor
       } else {
         None
       };
 
-      let value = Self { header, data, data_descriptor };
+      let value = Self {
+        header,
+        data,
+        data_descriptor,
+        incomplete: false,
+      };
 
       Ok(value)
     })
   }
 
+  pub fn read_partial<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_partial_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_partial_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
+    reader.trace(|reader| {
+      let initial_stream_position = reader.stream_position()?;
+
+      let missing_data_descriptor = match Self::read_with_options(reader, options) {
+        Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof => false,
+        Err(Error::MissingDataDescriptor) => true,
+        result => return result,
+      };
+
+      // Anything wrong with the header itself was already recorded by the attempt above.
+      reader.seek(SeekFrom::Start(initial_stream_position))?;
+      let header = LocalFileHeader::read_with_options(reader, &mut ParseOptions::lenient())?;
+      let start_of_data = reader.stream_position()?;
+      let remaining = reader.seek(SeekFrom::End(0))? - start_of_data;
+      reader.seek(SeekFrom::Start(start_of_data))?;
+      let compressed_size = if missing_data_descriptor {
+        Self::distance_to_next_record(reader)?.unwrap_or(remaining)
+      } else if header.indicates_data_descriptor_is_present() {
+        remaining
+      } else {
+        header.effective_compressed_size().min(remaining)
+      };
+
+      #[cfg(feature = "logging")]
+      if missing_data_descriptor {
+        log::warn!(
+          "{:?} has no data descriptor, keeping {compressed_size} bytes up to the next record",
+          header.decoded_file_name()
+        );
+      } else {
+        log::warn!("{:?} is truncated, keeping {compressed_size} bytes of compressed data", header.decoded_file_name());
+      }
+
+      let data = reader.read_vec(compressed_size.try_into()?)?;
+
+      let value = Self {
+        header,
+        data,
+        data_descriptor: None,
+        incomplete: true,
+      };
+
+      Ok(value)
+    })
+  }
+
+  fn distance_to_next_record<R: Read + Seek>(reader: &mut R) -> Result<Option<u64>> {
+    let initial_stream_position = reader.stream_position()?;
+
+    let mut nearest = None;
+    for signature in [LocalFileHeader::SIGNATURE, CentralDirectoryFileHeader::SIGNATURE] {
+      reader.seek(SeekFrom::Start(initial_stream_position))?;
+      if let Some(position) = reader.search(&signature)? {
+        nearest = Some(nearest.map_or(position, |nearest: u64| nearest.min(position)));
+      }
+    }
+
+    reader.seek(SeekFrom::Start(initial_stream_position))?;
+    Ok(nearest.map(|position| position - initial_stream_position))
+  }
+
   pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
     writer.trace(self.expected_size(), |writer| {
-      let Self { header, data, data_descriptor } = self;
+      let Self {
+        header,
+        data,
+        data_descriptor,
+        incomplete: _,
+      } = self;
 
       header.write(writer)?;
       writer.write_all(data)?;
@@ -67,20 +225,156 @@ impl Entry {
     })
   }
 
+  pub fn effective_header(&self) -> Result<LocalFileHeader> {
+    self.header.merged_with(self.data_descriptor)
+  }
+
+  pub fn effective_crc32_of_uncompressed_data(&self) -> u32 {
+    match self.data_descriptor {
+      Some(data_descriptor) if self.header.crc32_of_uncompressed_data == 0 => data_descriptor.crc32_of_uncompressed_data,
+      _ => self.header.crc32_of_uncompressed_data,
+    }
+  }
+
   pub fn decompress(&self) -> Result<Vec<u8>> {
-    let capacity = usize::try_from(self.header.uncompressed_size)?;
-    let mut uncompressed = Vec::with_capacity(capacity);
-    let mut compressed = DeflateDecoder::new(self.data.as_slice());
-    compressed.read_to_end(&mut uncompressed)?;
-
-    let crc32_of_uncompressed_data = crc32fast::hash(&uncompressed);
-    if crc32_of_uncompressed_data != self.header.crc32_of_uncompressed_data {
-      Ok(uncompressed)
+    self.decompress_with_verification(Verification::Strict)
+  }
+
+  pub fn decompress_with_verification(&self, verification: Verification) -> Result<Vec<u8>> {
+    let expected_size = match self.data_descriptor {
+      Some(data_descriptor) => data_descriptor.uncompressed_size,
+      None => self.header.effective_uncompressed_size(),
+    };
+    // The expected size comes straight from the header, so only reserve what the compressed data could plausibly expand to.
+    let capacity = expected_size
+      .min(u64::try_from(self.data.len())?.saturating_mul(MAXIMUM_COMPRESSION_RATIO))
+      .min(MAXIMUM_PREALLOCATION);
+    let mut uncompressed = Vec::with_capacity(usize::try_from(capacity)?);
+    let uncompressed_size = (!self.incomplete).then_some(expected_size);
+    let mut compressed = CompressionMethod::from(self.header.compression_method).decoder_with(self.data.as_slice(), &self.header, uncompressed_size)?;
+    match compressed.read_to_end(&mut uncompressed) {
+      Ok(_) => (),
+      Err(_error) if self.incomplete => {
+        #[cfg(feature = "logging")]
+        log::warn!(
+          "{:?} is incomplete, recovered {} bytes before {_error}",
+          self.header.decoded_file_name(),
+          uncompressed.len()
+        );
+      }
+      Err(error) => return Err(error.into()),
+    }
+
+    if verification == Verification::Skip || self.incomplete {
+      return Ok(uncompressed);
+    }
+
+    let expected = self.effective_crc32_of_uncompressed_data();
+    let found = crc32fast::hash(&uncompressed);
+    if expected == found {
+      return Ok(uncompressed);
+    }
+
+    match verification {
+      Verification::Strict => Err(Error::ChecksumMismatch { expected, found }),
+      // Without the logging feature the mismatch goes unreported, which the README points out.
+      _ => {
+        #[cfg(feature = "logging")]
+        log::warn!(
+          "checksum mismatch in {:?}: expected={expected:#010X?}, found={found:#010X?}",
+          self.header.decoded_file_name()
+        );
+        Ok(uncompressed)
+      }
+    }
+  }
+
+  pub fn completed(&self) -> Result<Self> {
+    if !self.incomplete {
+      return Ok(self.clone());
+    }
+
+    let uncompressed = self.decompress_with_verification(Verification::Skip)?;
+    let compression_method = match CompressionMethod::from(self.header.compression_method) {
+      compression_method @ (CompressionMethod::Stored | CompressionMethod::Deflated | CompressionMethod::Bzip2) => compression_method,
+      _ => CompressionMethod::Deflated,
+    };
+    let data = compression_method.compress(&uncompressed)?;
+
+    let mut header = self.header.clone();
+    if header.compression_method != u16::from(compression_method) {
+      header.compression_method = compression_method.into();
+      header.general_purpose_flags &= !0b00000110;
+    }
+    header.general_purpose_flags &= !0b00001000;
+    header.crc32_of_uncompressed_data = crc32fast::hash(&uncompressed);
+    header.set_sizes(data.len().try_into()?, uncompressed.len().try_into()?)?;
+
+    let value = Self {
+      header,
+      data,
+      data_descriptor: None,
+      incomplete: false,
+    };
+
+    Ok(value)
+  }
+}
+
+impl LocalEntry for EntryRef<'_> {
+  fn effective_header(&self) -> Result<LocalFileHeader> {
+    LocalFileHeader::from(self.header).merged_with(self.data_descriptor)
+  }
+
+  fn size_in_archive(&self) -> u64 {
+    self.expected_size()
+  }
+}
+
+impl<'a> EntryRef<'a> {
+  pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+    let header = LocalFileHeaderRef::parse(bytes)?;
+    let start_of_data = usize::try_from(header.expected_size())?;
+
+    let compressed_size = if header.indicates_data_descriptor_is_present() {
+      let mut cursor = Cursor::new(bytes);
+      cursor.set_position(header.expected_size());
+      boundary::find_end_of_data(
+        &mut cursor,
+        header.compression_method,
+        header.indicates_lzma_end_of_stream_marker(),
+        header.indicates_zip64(),
+      )?
+    } else {
+      header.effective_compressed_size()
+    };
+
+    let end_of_data = usize::try_from(compressed_size)
+      .ok()
+      .and_then(|compressed_size| start_of_data.checked_add(compressed_size))
+      .ok_or_else(Error::unexpected_eof)?;
+    let data = bytes.get(start_of_data..end_of_data).ok_or_else(Error::unexpected_eof)?;
+
+    let data_descriptor = if header.indicates_data_descriptor_is_present() {
+      let mut cursor = Cursor::new(&bytes[end_of_data..]);
+      Some(DataDescriptor::read_after(&mut cursor, header.indicates_zip64(), compressed_size)?)
     } else {
-      Err(Error::ChecksumMismatch {
-        expected: self.header.crc32_of_uncompressed_data,
-        found: crc32_of_uncompressed_data,
-      })
+      None
+    };
+
+    let value = Self { header, data, data_descriptor };
+
+    Ok(value)
+  }
+}
+
+impl From<EntryRef<'_>> for Entry {
+  fn from(EntryRef { header, data, data_descriptor }: EntryRef<'_>) -> Self {
+    Self {
+      header: header.into(),
+      data: data.to_vec(),
+      data_descriptor,
+      incomplete: false,
     }
   }
 }
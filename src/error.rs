@@ -6,14 +6,54 @@ pub enum Error {
   BadSignatureInEndOfCentralDirectoryHeader,
   #[error("bad signature in local file header")]
   BadSignatureInLocalFileHeader,
+  #[error("bad signature in zip64 end of central directory locator")]
+  BadSignatureInZip64EndOfCentralDirectoryLocator,
+  #[error("bad signature in zip64 end of central directory record")]
+  BadSignatureInZip64EndOfCentralDirectoryRecord,
   #[error("checksum mismatch: expected={expected:#010X?}, found={found:#010X?}")]
   ChecksumMismatch { expected: u32, found: u32 },
+  #[error("data descriptor conflicts with compressed data")]
+  DataDescriptorConflictsWithCompressedData,
   #[error("data descriptor conflicts with local file header")]
   DataDescriptorConflictsWithLocalFileHeader,
   #[error(transparent)]
+  Decompress(#[from] flate2::DecompressError),
+  #[error("implausible local file header")]
+  ImplausibleLocalFileHeader,
+  #[error("invalid compressed data for compression method {0}")]
+  InvalidCompressedData(u16),
+  #[error("invalid lzma properties size: {0}")]
+  InvalidLzmaProperties(u16),
+  #[error(transparent)]
   Io(#[from] std::io::Error),
+  #[error("unicode comment extra field does not match the file comment")]
+  MismatchedUnicodeComment,
+  #[error("unicode path extra field does not match the file name")]
+  MismatchedUnicodePath,
+  #[error("data descriptor not found after compressed data")]
+  MissingDataDescriptor,
+  #[error("end of central directory not found")]
+  MissingEndOfCentralDirectory,
+  #[error("data descriptor has no signature")]
+  MissingSignatureInDataDescriptor,
+  #[error("compression method {0} can't be decompressed without the uncompressed size")]
+  MissingUncompressedSize(u16),
+  #[error("comment truncated: expected {expected} bytes, found {found}")]
+  TruncatedComment { expected: u16, found: u16 },
   #[error(transparent)]
   TryFromInt(#[from] std::num::TryFromIntError),
+  #[error("file name is not valid {0:?}")]
+  UndecodableFileName(crate::FileNameEncoding),
+  #[error("unsupported compression method: {0}")]
+  UnsupportedCompressionMethod(u16),
   #[error(transparent)]
   Utf8(#[from] std::string::FromUtf8Error),
+  #[error(transparent)]
+  Utf8Slice(#[from] std::str::Utf8Error),
+}
+
+impl Error {
+  pub(crate) fn unexpected_eof() -> Self {
+    Self::Io(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))
+  }
 }
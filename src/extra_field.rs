@@ -0,0 +1,58 @@
+use crate::prelude::*;
+
+pub(crate) struct ExtraFields<'a> {
+  remaining: &'a [u8],
+}
+
+impl<'a> ExtraFields<'a> {
+  pub(crate) fn new(extra_field: &'a [u8]) -> Self {
+    Self { remaining: extra_field }
+  }
+}
+
+impl<'a> Iterator for ExtraFields<'a> {
+  type Item = (u16, &'a [u8]);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.remaining.len() < 4 {
+      return None;
+    }
+
+    let header_id = u16::from_le_bytes([self.remaining[0], self.remaining[1]]);
+    let data_size = usize::from(u16::from_le_bytes([self.remaining[2], self.remaining[3]]));
+    let data = self.remaining.get(4..4 + data_size)?;
+    self.remaining = &self.remaining[4 + data_size..];
+
+    Some((header_id, data))
+  }
+}
+
+pub(crate) fn find(extra_field: &[u8], header_id: u16) -> Option<&[u8]> {
+  ExtraFields::new(extra_field).find(|(id, _)| *id == header_id).map(|(_, data)| data)
+}
+
+pub(crate) fn replace(extra_field: &[u8], header_id: u16, data: Option<&[u8]>) -> Result<Vec<u8>> {
+  let mut replaced = Vec::with_capacity(extra_field.len());
+
+  let mut consumed = 0;
+  for (id, existing) in ExtraFields::new(extra_field) {
+    consumed += 4 + existing.len();
+    if id != header_id {
+      replaced.write_u16::<LittleEndian>(id)?;
+      replaced.write_u16::<LittleEndian>(existing.len().try_into()?)?;
+      replaced.write_all(existing)?;
+    }
+  }
+
+  if let Some(data) = data {
+    replaced.write_u16::<LittleEndian>(header_id)?;
+    replaced.write_u16::<LittleEndian>(data.len().try_into()?)?;
+    replaced.write_all(data)?;
+  }
+
+  // Anything that doesn't parse as a record (such as alignment padding) stays at the end, where it
+  // can't hide the records after it.
+  replaced.write_all(&extra_field[consumed..])?;
+
+  Ok(replaced)
+}
@@ -0,0 +1,177 @@
+use crate::cp437;
+use encoding_rs::{Encoding, EUC_KR, GBK, IBM866, SHIFT_JIS};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FileNameEncoding {
+  Auto,
+  Utf8,
+  #[default]
+  Cp437,
+  ShiftJis,
+  Gbk,
+  EucKr,
+  Cp866,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Detection {
+  pub encoding: FileNameEncoding,
+  pub confidence: f32,
+}
+
+impl FileNameEncoding {
+  const CANDIDATES: [Self; 6] = [Self::Utf8, Self::ShiftJis, Self::Gbk, Self::EucKr, Self::Cp866, Self::Cp437];
+
+  pub fn detect<'a, I: IntoIterator<Item = &'a [u8]>>(file_names: I) -> Detection {
+    let file_names: Vec<&[u8]> = file_names.into_iter().filter(|file_name| !file_name.is_ascii()).collect();
+
+    if file_names.is_empty() {
+      return Detection {
+        encoding: Self::Cp437,
+        confidence: 1.0,
+      };
+    }
+
+    if file_names.iter().all(|file_name| std::str::from_utf8(file_name).is_ok()) {
+      return Detection {
+        encoding: Self::Utf8,
+        confidence: 1.0,
+      };
+    }
+
+    let mut scores: Vec<(Self, f32)> = Self::CANDIDATES
+      .iter()
+      .filter_map(|&candidate| Some((candidate, candidate.score(&file_names)?)))
+      .collect();
+    scores.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    match scores.as_slice() {
+      [(encoding, best), (_, runner_up), ..] => Detection {
+        encoding: *encoding,
+        confidence: best - runner_up,
+      },
+      [(encoding, best)] => Detection {
+        encoding: *encoding,
+        confidence: *best,
+      },
+      [] => Detection {
+        encoding: Self::Cp437,
+        confidence: 0.0,
+      },
+    }
+  }
+
+  pub fn decode(&self, file_name: &[u8]) -> String {
+    match self {
+      Self::Auto => Self::detect([file_name]).encoding.decode(file_name),
+      Self::Utf8 => String::from_utf8_lossy(file_name).into_owned(),
+      Self::Cp437 => cp437::decode(file_name),
+      Self::ShiftJis | Self::Gbk | Self::EucKr | Self::Cp866 => self
+        .encoding()
+        .map(|encoding| encoding.decode_without_bom_handling(file_name).0.into_owned())
+        .unwrap_or_default(),
+    }
+  }
+
+  pub(crate) fn can_decode(&self, file_name: &[u8]) -> bool {
+    match self {
+      Self::Auto => Self::detect([file_name]).encoding.can_decode(file_name),
+      _ => self.decode_strictly(file_name).is_some(),
+    }
+  }
+
+  fn encoding(&self) -> Option<&'static Encoding> {
+    match self {
+      Self::ShiftJis => Some(SHIFT_JIS),
+      Self::Gbk => Some(GBK),
+      Self::EucKr => Some(EUC_KR),
+      Self::Cp866 => Some(IBM866),
+      Self::Auto | Self::Utf8 | Self::Cp437 => None,
+    }
+  }
+
+  fn decode_strictly(&self, file_name: &[u8]) -> Option<String> {
+    match self {
+      Self::Auto => None,
+      Self::Utf8 => std::str::from_utf8(file_name).ok().map(str::to_owned),
+      Self::Cp437 => Some(cp437::decode(file_name)),
+      _ => self
+        .encoding()?
+        .decode_without_bom_handling_and_without_replacement(file_name)
+        .map(|decoded| decoded.into_owned()),
+    }
+  }
+
+  fn score(&self, file_names: &[&[u8]]) -> Option<f32> {
+    let mut total = 0.0;
+    let mut weighted = 0.0;
+    for file_name in file_names {
+      let characters: Vec<char> = self.decode_strictly(file_name)?.chars().collect();
+      for (index, character) in characters.iter().enumerate() {
+        if character.is_ascii() {
+          continue;
+        }
+        let adjacent_to_latin = [index.checked_sub(1), Some(index + 1)]
+          .into_iter()
+          .flatten()
+          .filter_map(|index| characters.get(index))
+          .any(char::is_ascii_alphabetic);
+        // Shift_JIS lead bytes read as capital Cyrillic letters in CP866, which rarely follow another letter in real names.
+        let follows_cyrillic_letter = index
+          .checked_sub(1)
+          .and_then(|index| characters.get(index))
+          .is_some_and(|previous| previous.is_alphabetic() && !previous.is_ascii());
+        total += 1.0;
+        weighted += match self {
+          Self::Utf8 | Self::Cp437 => self.weight(*character),
+          Self::Cp866 if character.is_uppercase() && follows_cyrillic_letter => self.weight(*character) / 2.0,
+          _ if adjacent_to_latin => self.weight(*character) / 4.0,
+          _ => self.weight(*character),
+        };
+      }
+    }
+    Some(if total > 0.0 { weighted / total } else { 0.0 })
+  }
+
+  fn weight(&self, character: char) -> f32 {
+    let lead_byte = |encoding: &'static Encoding| encoding.encode(character.encode_utf8(&mut [0u8; 4])).0.first().copied().unwrap_or_default();
+    let punctuation = matches!(character, '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF5E}');
+    let ideograph = matches!(character, '\u{4E00}'..='\u{9FFF}');
+
+    match self {
+      Self::Auto => 0.0,
+      Self::Utf8 => 1.0,
+      Self::ShiftJis => match character {
+        '\u{3041}'..='\u{30FF}' => 1.0,
+        '\u{FF61}'..='\u{FF9F}' => 0.25,
+        _ if ideograph && (0x88..=0x9F).contains(&lead_byte(SHIFT_JIS)) => 1.0,
+        _ if ideograph || punctuation => 0.75,
+        _ => 0.0,
+      },
+      Self::Gbk => match character {
+        _ if ideograph && (0xC9..=0xF7).contains(&lead_byte(GBK)) => 1.0,
+        _ if ideograph && (0xB0..=0xC8).contains(&lead_byte(GBK)) => 0.85,
+        _ if punctuation => 0.75,
+        _ if ideograph => 0.25,
+        _ => 0.0,
+      },
+      Self::EucKr => match character {
+        '\u{AC00}'..='\u{D7A3}' if (0xB0..=0xC8).contains(&lead_byte(EUC_KR)) => 1.0,
+        '\u{AC00}'..='\u{D7A3}' => 0.25,
+        _ if punctuation => 0.75,
+        _ if ideograph => 0.25,
+        _ => 0.0,
+      },
+      Self::Cp866 => match character {
+        '\u{0401}' | '\u{0410}'..='\u{044F}' | '\u{0451}' => 1.0,
+        '\u{2500}'..='\u{25FF}' => -1.0,
+        _ => 0.0,
+      },
+      Self::Cp437 => match character {
+        '\u{00C0}'..='\u{00FF}' if character != '\u{00D7}' && character != '\u{00F7}' => 0.5,
+        '\u{2500}'..='\u{25FF}' => -1.0,
+        _ => 0.0,
+      },
+    }
+  }
+}
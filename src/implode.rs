@@ -0,0 +1,115 @@
+use crate::bit_reader::BitReader;
+use crate::prelude::*;
+
+const MAXIMUM_BIT_LENGTH: usize = 16;
+
+// Shannon-Fano trees are canonical prefix codes with every bit inverted, so they decode like
+// Deflate's Huffman codes once each bit read from the stream is flipped.
+struct ShannonFanoTree {
+  counts: [u16; MAXIMUM_BIT_LENGTH + 1],
+  symbols: Vec<u16>,
+}
+
+impl ShannonFanoTree {
+  fn read(reader: &mut BitReader, number_of_symbols: usize) -> Option<Self> {
+    let number_of_bytes = reader.read(8)? + 1;
+    let mut bit_lengths = Vec::with_capacity(number_of_symbols);
+    for _ in 0..number_of_bytes {
+      let byte = reader.read(8)?;
+      let bit_length = (byte & 0x0F) as usize + 1;
+      let count = (byte >> 4) + 1;
+      bit_lengths.extend(std::iter::repeat_n(bit_length, count as usize));
+    }
+    if bit_lengths.len() != number_of_symbols {
+      return None;
+    }
+
+    let mut counts = [0u16; MAXIMUM_BIT_LENGTH + 1];
+    for bit_length in &bit_lengths {
+      counts[*bit_length] += 1;
+    }
+
+    let mut symbols = Vec::with_capacity(number_of_symbols);
+    for bit_length in 1..=MAXIMUM_BIT_LENGTH {
+      symbols.extend((0..number_of_symbols).filter(|symbol| bit_lengths[*symbol] == bit_length).map(|symbol| symbol as u16));
+    }
+
+    Some(Self { counts, symbols })
+  }
+
+  fn decode(&self, reader: &mut BitReader) -> Option<Result<u16>> {
+    let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+    for count in &self.counts[1..] {
+      let count = i32::from(*count);
+      code |= (reader.read(1)? ^ 1) as i32;
+      if code - first < count {
+        return Some(Ok(self.symbols[(index + code - first) as usize]));
+      }
+      index += count;
+      first = (first + count) << 1;
+      code <<= 1;
+    }
+    Some(Err(Error::InvalidCompressedData(6)))
+  }
+}
+
+pub(crate) fn explode(compressed: &[u8], general_purpose_flags: u16, uncompressed_size: usize) -> Result<Vec<u8>> {
+  let invalid = || Error::InvalidCompressedData(6);
+  let large_dictionary = general_purpose_flags & 0b00000010 != 0;
+  let literal_tree_is_present = general_purpose_flags & 0b00000100 != 0;
+  let distance_low_bits = if large_dictionary { 7 } else { 6 };
+  let minimum_match_length = if literal_tree_is_present { 3 } else { 2 };
+
+  let mut reader = BitReader::new(compressed);
+  let mut output = Vec::new();
+
+  let literal_tree = match literal_tree_is_present {
+    true => Some(ShannonFanoTree::read(&mut reader, 256).ok_or_else(invalid)?),
+    false => None,
+  };
+  let length_tree = ShannonFanoTree::read(&mut reader, 64).ok_or_else(invalid)?;
+  let distance_tree = ShannonFanoTree::read(&mut reader, 64).ok_or_else(invalid)?;
+
+  while output.len() < uncompressed_size {
+    let Some(is_literal) = reader.read(1) else {
+      break;
+    };
+
+    if is_literal == 1 {
+      let literal = match &literal_tree {
+        Some(literal_tree) => literal_tree.decode(&mut reader).transpose()?.map(u32::from),
+        None => reader.read(8),
+      };
+      let Some(literal) = literal else {
+        break;
+      };
+      output.push(literal as u8);
+      continue;
+    }
+
+    let Some(distance_low) = reader.read(distance_low_bits) else {
+      break;
+    };
+    let Some(distance_high) = distance_tree.decode(&mut reader).transpose()? else {
+      break;
+    };
+    let Some(mut length) = length_tree.decode(&mut reader).transpose()?.map(usize::from) else {
+      break;
+    };
+    if length == 63 {
+      let Some(extra) = reader.read(8) else {
+        break;
+      };
+      length += extra as usize;
+    }
+
+    let distance = ((usize::from(distance_high) << distance_low_bits) | distance_low as usize) + 1;
+    for _ in 0..length + minimum_match_length {
+      output.push(output.len().checked_sub(distance).map_or(0, |position| output[position]));
+    }
+  }
+
+  output.truncate(uncompressed_size);
+
+  Ok(output)
+}
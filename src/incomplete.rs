@@ -0,0 +1,6 @@
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Incomplete {
+  #[default]
+  Exclude,
+  Include,
+}
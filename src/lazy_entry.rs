@@ -0,0 +1,117 @@
+use super::{CompressionMethod, DataDescriptor, Entry, LocalEntry, LocalFileHeader, ParseOptions};
+use crate::boundary;
+use crate::prelude::*;
+use std::io::{SeekFrom, Take};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LazyEntry {
+  pub header: LocalFileHeader,
+  pub data_offset: u64,
+  pub data_length: u64,
+  pub data_descriptor: Option<DataDescriptor>,
+}
+
+impl ExpectedSize for LazyEntry {
+  fn expected_size(&self) -> u64 {
+    self.header.expected_size()
+      + self.data_length
+      + match self.data_descriptor {
+        Some(data_descriptor) => data_descriptor.expected_size(),
+        None => 0,
+      }
+  }
+}
+
+impl LocalEntry for LazyEntry {
+  fn effective_header(&self) -> Result<LocalFileHeader> {
+    LazyEntry::effective_header(self)
+  }
+
+  fn size_in_archive(&self) -> u64 {
+    self.expected_size()
+  }
+}
+
+impl LazyEntry {
+  pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
+    reader.trace(|reader| {
+      let offset = reader.stream_position()?;
+      let mut header = LocalFileHeader::read_with_options(reader, options)?;
+      let data_offset = reader.stream_position()?;
+
+      let data_length = if header.indicates_data_descriptor_is_present() {
+        boundary::find_end_of_data(
+          reader,
+          header.compression_method,
+          header.indicates_lzma_end_of_stream_marker(),
+          header.indicates_zip64(),
+        )?
+      } else {
+        header.effective_compressed_size()
+      };
+
+      let end_of_data = data_offset + data_length;
+      if end_of_data > reader.seek(SeekFrom::End(0))? {
+        return Err(Error::unexpected_eof());
+      }
+      reader.seek(SeekFrom::Start(end_of_data))?;
+
+      let data_descriptor = if header.indicates_data_descriptor_is_present() {
+        let data_descriptor = DataDescriptor::try_read_after(reader, header.indicates_zip64(), data_length, options)?;
+        match data_descriptor {
+          Some(data_descriptor) => header.reconcile_with_options(data_descriptor, offset, options)?,
+          None => {
+            let end_of_data_descriptor = reader.stream_position()?;
+            reader.seek(SeekFrom::Start(data_offset))?;
+            let measured = boundary::measure_uncompressed_data(reader.by_ref().take(data_length), &header, data_length)?;
+            header.update_without_data_descriptor_with_options(measured, offset, options)?;
+            reader.seek(SeekFrom::Start(end_of_data_descriptor))?;
+          }
+        }
+        data_descriptor
+      } else {
+        None
+      };
+
+      let value = Self {
+        header,
+        data_offset,
+        data_length,
+        data_descriptor,
+      };
+
+      Ok(value)
+    })
+  }
+
+  pub fn effective_header(&self) -> Result<LocalFileHeader> {
+    self.header.merged_with(self.data_descriptor)
+  }
+
+  pub fn raw<'a, R: Read + Seek>(&self, reader: &'a mut R) -> Result<Take<&'a mut R>> {
+    reader.seek(SeekFrom::Start(self.data_offset))?;
+    Ok(reader.take(self.data_length))
+  }
+
+  pub fn decompressor<'a, R: Read + Seek>(&self, reader: &'a mut R) -> Result<Box<dyn Read + 'a>> {
+    let header = self.effective_header()?;
+    CompressionMethod::from(header.compression_method).decoder_with(self.raw(reader)?, &header, Some(header.effective_uncompressed_size()))
+  }
+
+  pub fn load<R: Read + Seek>(&self, reader: &mut R) -> Result<Entry> {
+    let data = self.raw(reader)?.read_vec(self.data_length.try_into()?)?;
+
+    let value = Entry {
+      header: self.header.clone(),
+      data,
+      data_descriptor: self.data_descriptor,
+      incomplete: false,
+    };
+
+    Ok(value)
+  }
+}
@@ -1,34 +1,93 @@
+mod bit_reader;
+mod boundary;
+mod carve;
 mod central_directory;
 mod central_directory_file_header;
+mod compression_method;
+mod cp437;
 mod data_descriptor;
 mod end_of_central_directory;
 mod entry;
 mod error;
+mod extra_field;
+mod file_name_encoding;
+mod implode;
+mod incomplete;
+mod lazy_entry;
 mod local_file_header;
+mod lzma;
+mod offsets;
+mod parse_options;
+mod reduce;
+mod repair;
+mod scan;
+mod search;
+mod shrink;
+mod unicode_comment_extra_field;
+mod unicode_path_extra_field;
+mod verification;
+mod zip64_end_of_central_directory;
+mod zip64_end_of_central_directory_locator;
+mod zip64_extended_information;
 
 pub(crate) mod prelude {
   pub(crate) use crate::error::Error;
-  pub(crate) use crate::{ExpectedSize, ReadTracing, Result, WriteTracing};
+  pub(crate) use crate::{ExpectedSize, ReadTracing, ReadVec, Result, WriteTracing};
   pub(crate) use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
   pub(crate) use std::io::prelude::*;
 }
 
 use prelude::*;
 
+pub use carve::{Carve, Carved};
 pub use central_directory::CentralDirectory;
-pub use central_directory_file_header::CentralDirectoryFileHeader;
+pub use central_directory_file_header::{CentralDirectoryFileHeader, CentralDirectoryFileHeaderRef};
+pub use compression_method::CompressionMethod;
 pub use data_descriptor::DataDescriptor;
 pub use end_of_central_directory::EndOfCentralDirectory;
-pub use entry::Entry;
+pub use entry::{Entry, EntryRef};
 pub use error::Error;
-pub use local_file_header::LocalFileHeader;
+pub use file_name_encoding::{Detection, FileNameEncoding};
+pub use incomplete::Incomplete;
+pub use lazy_entry::LazyEntry;
+pub use local_file_header::{LocalFileHeader, LocalFileHeaderRef};
+pub use lzma::LzmaHeader;
+pub use offsets::Offsets;
+pub use parse_options::{Diagnostic, ParseOptions, Strictness};
+pub use repair::Repair;
+pub use scan::{Scan, Stop};
+pub use unicode_comment_extra_field::UnicodeCommentExtraField;
+pub use unicode_path_extra_field::UnicodePathExtraField;
+pub use verification::Verification;
+pub use zip64_end_of_central_directory::Zip64EndOfCentralDirectory;
+pub use zip64_end_of_central_directory_locator::Zip64EndOfCentralDirectoryLocator;
+pub use zip64_extended_information::Zip64ExtendedInformation;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+pub trait LocalEntry {
+  fn effective_header(&self) -> Result<LocalFileHeader>;
+  fn size_in_archive(&self) -> u64;
+}
+
 pub(crate) trait ExpectedSize {
-  fn expected_size(&self) -> u32;
+  fn expected_size(&self) -> u64;
 }
 
+pub(crate) trait ReadVec: Read {
+  // Lengths often come from untrusted headers, so grow the buffer as data arrives instead of allocating it up front.
+  fn read_vec(&mut self, length: usize) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    (&mut *self).take(length.try_into()?).read_to_end(&mut buffer)?;
+    if buffer.len() < length {
+      return Err(Error::unexpected_eof());
+    }
+    Ok(buffer)
+  }
+}
+
+impl<T> ReadVec for T where T: Read + ?Sized {}
+
 pub(crate) trait ReadTracing: Read + Seek {
   fn trace<F: FnMut(&mut Self) -> Result<T>, T: ExpectedSize>(&mut self, mut function: F) -> Result<T> {
     #[cfg(not(feature = "logging"))]
@@ -45,7 +104,7 @@ pub(crate) trait ReadTracing: Read + Seek {
       #[cfg(feature = "discovery")]
       {
         log::trace!("read size={}, expected={}", final_stream_position - initial_stream_position, value.expected_size());
-        let expected_stream_position = initial_stream_position + u64::from(value.expected_size());
+        let expected_stream_position = initial_stream_position + value.expected_size();
         if final_stream_position != expected_stream_position {
           log::warn!("read expected to end at {:#010X}, not {:#010X}", expected_stream_position, final_stream_position);
         }
@@ -65,7 +124,7 @@ impl<T> ReadTracing for T where T: Read + Seek {}
 
 pub(crate) trait WriteTracing: Write + Seek {
   #[cfg_attr(not(feature = "discovery"), allow(unused_variables))]
-  fn trace<F: FnMut(&mut Self) -> Result<T>, T>(&mut self, expected_size: u32, mut function: F) -> Result<T> {
+  fn trace<F: FnMut(&mut Self) -> Result<T>, T>(&mut self, expected_size: u64, mut function: F) -> Result<T> {
     #[cfg(not(feature = "logging"))]
     return function(self);
 
@@ -80,7 +139,7 @@ pub(crate) trait WriteTracing: Write + Seek {
       #[cfg(feature = "discovery")]
       {
         log::trace!("write size={}, expected={}", final_stream_position - initial_stream_position, expected_size);
-        let expected_stream_position = initial_stream_position + u64::from(expected_size);
+        let expected_stream_position = initial_stream_position + expected_size;
         if final_stream_position != expected_stream_position {
           log::warn!("write expected to end at {:#010X}, not {:#010X}", expected_stream_position, final_stream_position);
         }
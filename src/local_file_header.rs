@@ -1,4 +1,5 @@
-use super::DataDescriptor;
+use super::{DataDescriptor, FileNameEncoding, ParseOptions, UnicodePathExtraField, Zip64ExtendedInformation};
+use crate::extra_field;
 use crate::prelude::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -14,18 +15,41 @@ pub struct LocalFileHeader {
   pub uncompressed_size: u32,
   pub file_name_length: u16,
   pub extra_field_length: u16,
-  pub file_name: String,
+  pub file_name: Vec<u8>,
   pub extra_field: Vec<u8>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LocalFileHeaderRef<'a> {
+  pub signature: [u8; 4],
+  pub version_needed_to_extract: u16,
+  pub general_purpose_flags: u16,
+  pub compression_method: u16,
+  pub file_last_modification_time: u16,
+  pub file_last_modification_date: u16,
+  pub crc32_of_uncompressed_data: u32,
+  pub compressed_size: u32,
+  pub uncompressed_size: u32,
+  pub file_name_length: u16,
+  pub extra_field_length: u16,
+  pub file_name: &'a [u8],
+  pub extra_field: &'a [u8],
+}
+
 impl ExpectedSize for LocalFileHeader {
-  fn expected_size(&self) -> u32 {
-    30 + u32::from(self.file_name_length) + u32::from(self.extra_field_length)
+  fn expected_size(&self) -> u64 {
+    30 + u64::from(self.file_name_length) + u64::from(self.extra_field_length)
+  }
+}
+
+impl ExpectedSize for LocalFileHeaderRef<'_> {
+  fn expected_size(&self) -> u64 {
+    30 + u64::from(self.file_name_length) + u64::from(self.extra_field_length)
   }
 }
 
 impl LocalFileHeader {
-  const SIGNATURE: [u8; 4] = [0x50, 0x4B, 0x03, 0x04];
+  pub const SIGNATURE: [u8; 4] = [0x50, 0x4B, 0x03, 0x04];
 
   pub fn has_valid_signature(&self) -> bool {
     self.signature == Self::SIGNATURE
@@ -35,18 +59,135 @@ impl LocalFileHeader {
     crc32fast::hash(uncompressed) == self.crc32_of_uncompressed_data
   }
 
+  pub fn indicates_utf8(&self) -> bool {
+    self.general_purpose_flags & 0b0000100000000000 != 0
+  }
+
+  pub fn decoded_file_name(&self) -> String {
+    self.decoded_file_name_with(FileNameEncoding::default())
+  }
+
+  pub fn decoded_file_name_with(&self, encoding: FileNameEncoding) -> String {
+    if self.indicates_utf8() {
+      FileNameEncoding::Utf8.decode(&self.file_name)
+    } else {
+      encoding.decode(&self.file_name)
+    }
+  }
+
+  pub fn unicode_path(&self) -> Option<UnicodePathExtraField> {
+    extra_field::find(&self.extra_field, UnicodePathExtraField::HEADER_ID).and_then(UnicodePathExtraField::parse)
+  }
+
+  pub fn effective_file_name(&self) -> String {
+    self.effective_file_name_with(FileNameEncoding::default())
+  }
+
+  pub fn effective_file_name_with(&self, encoding: FileNameEncoding) -> String {
+    match self.unicode_path() {
+      Some(unicode_path) if unicode_path.applies_to(&self.file_name) => String::from_utf8_lossy(&unicode_path.unicode_name).into_owned(),
+      _ => self.decoded_file_name_with(encoding),
+    }
+  }
+
   pub fn indicates_data_descriptor_is_present(&self) -> bool {
     self.general_purpose_flags & 0b00001000 != 0
   }
 
-  pub fn update(
+  pub fn indicates_lzma_end_of_stream_marker(&self) -> bool {
+    self.general_purpose_flags & 0b00000010 != 0
+  }
+
+  pub fn indicates_zip64(&self) -> bool {
+    self.zip64_extended_information().is_some()
+  }
+
+  pub fn zip64_extended_information(&self) -> Option<Zip64ExtendedInformation> {
+    extra_field::find(&self.extra_field, Zip64ExtendedInformation::HEADER_ID).map(|data| Zip64ExtendedInformation::parse(data, true, true, false, false))
+  }
+
+  pub fn effective_compressed_size(&self) -> u64 {
+    match self.zip64_extended_information() {
+      Some(Zip64ExtendedInformation {
+        compressed_size: Some(compressed_size),
+        ..
+      }) if self.compressed_size == u32::MAX => compressed_size,
+      _ => self.compressed_size.into(),
+    }
+  }
+
+  pub fn effective_uncompressed_size(&self) -> u64 {
+    match self.zip64_extended_information() {
+      Some(Zip64ExtendedInformation {
+        uncompressed_size: Some(uncompressed_size),
+        ..
+      }) if self.uncompressed_size == u32::MAX => uncompressed_size,
+      _ => self.uncompressed_size.into(),
+    }
+  }
+
+  pub fn set_sizes(&mut self, compressed_size: u64, uncompressed_size: u64) -> Result<()> {
+    if compressed_size < u32::MAX.into() && uncompressed_size < u32::MAX.into() && !self.indicates_zip64() {
+      self.compressed_size = compressed_size.try_into()?;
+      self.uncompressed_size = uncompressed_size.try_into()?;
+      return Ok(());
+    }
+
+    let zip64_extended_information = Zip64ExtendedInformation {
+      uncompressed_size: Some(uncompressed_size),
+      compressed_size: Some(compressed_size),
+      ..Default::default()
+    };
+
+    self.compressed_size = u32::MAX;
+    self.uncompressed_size = u32::MAX;
+    self.version_needed_to_extract = self.version_needed_to_extract.max(Zip64ExtendedInformation::VERSION_NEEDED_TO_EXTRACT);
+    self.set_extra_field(extra_field::replace(
+      &self.extra_field,
+      Zip64ExtendedInformation::HEADER_ID,
+      Some(&zip64_extended_information.to_bytes()?),
+    )?)
+  }
+
+  pub fn transcode_to_utf8(&mut self, encoding: FileNameEncoding) -> Result<()> {
+    if self.indicates_utf8() {
+      return Ok(());
+    }
+
+    let file_name = self.effective_file_name_with(encoding).into_bytes();
+    self.set_file_name(file_name)?;
+    self.set_extra_field(extra_field::replace(&self.extra_field, UnicodePathExtraField::HEADER_ID, None)?)?;
+    self.general_purpose_flags |= 0b0000100000000000;
+    Ok(())
+  }
+
+  pub fn set_file_name(&mut self, file_name: Vec<u8>) -> Result<()> {
+    self.file_name_length = file_name.len().try_into()?;
+    self.file_name = file_name;
+    Ok(())
+  }
+
+  fn set_extra_field(&mut self, extra_field: Vec<u8>) -> Result<()> {
+    self.extra_field_length = extra_field.len().try_into()?;
+    self.extra_field = extra_field;
+    Ok(())
+  }
+
+  pub fn update(&mut self, data_descriptor: DataDescriptor) -> Result<()> {
+    self.update_with_options(data_descriptor, 0, &mut ParseOptions::default())
+  }
+
+  pub fn update_with_options(
     &mut self,
     DataDescriptor {
       signature: _,
       crc32_of_uncompressed_data,
       compressed_size,
       uncompressed_size,
+      zip64: _,
     }: DataDescriptor,
+    offset: u64,
+    options: &mut ParseOptions,
   ) -> Result<()> {
     if self.crc32_of_uncompressed_data == 0 {
       #[cfg(feature = "logging")]
@@ -56,68 +197,102 @@ impl LocalFileHeader {
       #[cfg(feature = "logging")]
       log::trace!("CRC-32 in Local File Header matches with Trailing Data Descriptor: {crc32_of_uncompressed_data:#X?}");
     } else {
-      return Err(Error::DataDescriptorConflictsWithLocalFileHeader);
+      options.recover(offset, Error::DataDescriptorConflictsWithLocalFileHeader)?;
+      self.crc32_of_uncompressed_data = crc32_of_uncompressed_data;
     }
 
-    if self.compressed_size == 0 {
+    let mut effective_compressed_size = self.effective_compressed_size();
+    if effective_compressed_size == 0 {
       #[cfg(feature = "logging")]
       log::debug!("Compressed Size in Local File Header updated from Trailing Data Descriptor: {compressed_size}");
-      self.compressed_size = compressed_size;
-    } else if self.compressed_size == compressed_size {
+      effective_compressed_size = compressed_size;
+    } else if effective_compressed_size == compressed_size {
       #[cfg(feature = "logging")]
       log::trace!("Compressed Size in Local File Header matches with Trailing Data Descriptor")
     } else {
-      return Err(Error::DataDescriptorConflictsWithLocalFileHeader);
+      options.recover(offset, Error::DataDescriptorConflictsWithLocalFileHeader)?;
+      effective_compressed_size = compressed_size;
     }
 
-    if self.uncompressed_size == 0 {
+    let mut effective_uncompressed_size = self.effective_uncompressed_size();
+    if effective_uncompressed_size == 0 {
       #[cfg(feature = "logging")]
       log::debug!("Uncompressed Size in Local File Header updated from Trailing Data Descriptor: {uncompressed_size}");
-      self.uncompressed_size = uncompressed_size;
-    } else if self.uncompressed_size == uncompressed_size {
+      effective_uncompressed_size = uncompressed_size;
+    } else if effective_uncompressed_size == uncompressed_size {
       #[cfg(feature = "logging")]
       log::trace!("Uncompressed Size in Local File Header matches with Trailing Data Descriptor")
     } else {
-      return Err(Error::DataDescriptorConflictsWithLocalFileHeader);
+      options.recover(offset, Error::DataDescriptorConflictsWithLocalFileHeader)?;
+      effective_uncompressed_size = uncompressed_size;
     }
 
+    self.set_sizes(effective_compressed_size, effective_uncompressed_size)
+  }
+
+  pub fn update_without_data_descriptor(&mut self, data_descriptor: DataDescriptor) -> Result<()> {
+    self.update_without_data_descriptor_with_options(data_descriptor, 0, &mut ParseOptions::default())
+  }
+
+  pub fn update_without_data_descriptor_with_options(&mut self, data_descriptor: DataDescriptor, offset: u64, options: &mut ParseOptions) -> Result<()> {
+    self.update_with_options(data_descriptor, offset, options)?;
+    self.general_purpose_flags &= !0b00001000;
     Ok(())
   }
 
+  // A header that conflicts with its Data Descriptor is brought in line with it, so that later
+  // merges (such as adding the entry to a Central Directory) don't trip over the same conflict.
+  pub(crate) fn reconcile_with_options(&mut self, data_descriptor: DataDescriptor, offset: u64, options: &mut ParseOptions) -> Result<()> {
+    match self.merged_with(Some(data_descriptor)) {
+      Err(Error::DataDescriptorConflictsWithLocalFileHeader) => self.update_with_options(data_descriptor, offset, options),
+      result => result.map(|_| ()),
+    }
+  }
+
+  pub(crate) fn merged_with(&self, data_descriptor: Option<DataDescriptor>) -> Result<Self> {
+    let mut header = self.clone();
+    if header.indicates_data_descriptor_is_present() {
+      if let Some(data_descriptor) = data_descriptor {
+        header.update(data_descriptor)?;
+      }
+    }
+    Ok(header)
+  }
+
   pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
     reader.trace(|reader| {
+      let offset = reader.stream_position()?;
+      let mut fixed = [0u8; 30];
+      reader.read_exact(&mut fixed)?;
+      let mut fields = fixed.as_slice();
+
       let mut signature = [0u8; 4];
-      reader.read_exact(&mut signature)?;
+      fields.read_exact(&mut signature)?;
 
       if signature != Self::SIGNATURE {
         #[cfg(feature = "logging")]
         log::error!("read signature={signature:?} != {:?}", Self::SIGNATURE);
-        return Err(Error::BadSignatureInLocalFileHeader);
+        options.recover(offset, Error::BadSignatureInLocalFileHeader)?;
       }
 
-      let version_needed_to_extract = reader.read_u16::<LittleEndian>()?;
-      let general_purpose_flags = reader.read_u16::<LittleEndian>()?;
-      let compression_method = reader.read_u16::<LittleEndian>()?;
-      let file_last_modification_time = reader.read_u16::<LittleEndian>()?;
-      let file_last_modification_date = reader.read_u16::<LittleEndian>()?;
-      let crc32_of_uncompressed_data = reader.read_u32::<LittleEndian>()?;
-      let compressed_size = reader.read_u32::<LittleEndian>()?;
-      let uncompressed_size = reader.read_u32::<LittleEndian>()?;
-      let file_name_length = reader.read_u16::<LittleEndian>()?;
-      let extra_field_length = reader.read_u16::<LittleEndian>()?;
-
-      let file_name = {
-        let mut file_name = Vec::with_capacity(file_name_length.into());
-        for _ in 0..file_name_length {
-          file_name.push(reader.read_u8()?);
-        }
-        String::from_utf8(file_name)?
-      };
+      let version_needed_to_extract = fields.read_u16::<LittleEndian>()?;
+      let general_purpose_flags = fields.read_u16::<LittleEndian>()?;
+      let compression_method = fields.read_u16::<LittleEndian>()?;
+      let file_last_modification_time = fields.read_u16::<LittleEndian>()?;
+      let file_last_modification_date = fields.read_u16::<LittleEndian>()?;
+      let crc32_of_uncompressed_data = fields.read_u32::<LittleEndian>()?;
+      let compressed_size = fields.read_u32::<LittleEndian>()?;
+      let uncompressed_size = fields.read_u32::<LittleEndian>()?;
+      let file_name_length = fields.read_u16::<LittleEndian>()?;
+      let extra_field_length = fields.read_u16::<LittleEndian>()?;
 
-      let mut extra_field = Vec::with_capacity(extra_field_length.into());
-      for _ in 0..extra_field_length {
-        extra_field.push(reader.read_u8()?);
-      }
+      let mut file_name = reader.read_vec(usize::from(file_name_length) + usize::from(extra_field_length))?;
+      let extra_field = file_name.split_off(file_name_length.into());
+      options.check_file_name(offset, &file_name, &extra_field, general_purpose_flags)?;
 
       let value = Self {
         signature,
@@ -172,10 +347,152 @@ impl LocalFileHeader {
       writer.write_u32::<LittleEndian>(*uncompressed_size)?;
       writer.write_u16::<LittleEndian>(*file_name_length)?;
       writer.write_u16::<LittleEndian>(*extra_field_length)?;
-      writer.write_all(file_name.as_bytes())?;
+      writer.write_all(file_name)?;
       writer.write_all(extra_field)?;
 
       Ok(())
     })
   }
 }
+
+impl<'a> LocalFileHeaderRef<'a> {
+  pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+    let mut fields = bytes.get(..30).ok_or_else(Error::unexpected_eof)?;
+
+    let mut signature = [0u8; 4];
+    fields.read_exact(&mut signature)?;
+
+    if signature != LocalFileHeader::SIGNATURE {
+      #[cfg(feature = "logging")]
+      log::error!("parse signature={signature:?} != {:?}", LocalFileHeader::SIGNATURE);
+      return Err(Error::BadSignatureInLocalFileHeader);
+    }
+
+    let version_needed_to_extract = fields.read_u16::<LittleEndian>()?;
+    let general_purpose_flags = fields.read_u16::<LittleEndian>()?;
+    let compression_method = fields.read_u16::<LittleEndian>()?;
+    let file_last_modification_time = fields.read_u16::<LittleEndian>()?;
+    let file_last_modification_date = fields.read_u16::<LittleEndian>()?;
+    let crc32_of_uncompressed_data = fields.read_u32::<LittleEndian>()?;
+    let compressed_size = fields.read_u32::<LittleEndian>()?;
+    let uncompressed_size = fields.read_u32::<LittleEndian>()?;
+    let file_name_length = fields.read_u16::<LittleEndian>()?;
+    let extra_field_length = fields.read_u16::<LittleEndian>()?;
+
+    let end_of_file_name = 30 + usize::from(file_name_length);
+    let end_of_extra_field = end_of_file_name + usize::from(extra_field_length);
+    let file_name = bytes.get(30..end_of_file_name).ok_or_else(Error::unexpected_eof)?;
+    let extra_field = bytes.get(end_of_file_name..end_of_extra_field).ok_or_else(Error::unexpected_eof)?;
+
+    let value = Self {
+      signature,
+      version_needed_to_extract,
+      general_purpose_flags,
+      compression_method,
+      file_last_modification_time,
+      file_last_modification_date,
+      crc32_of_uncompressed_data,
+      compressed_size,
+      uncompressed_size,
+      file_name_length,
+      extra_field_length,
+      file_name,
+      extra_field,
+    };
+
+    Ok(value)
+  }
+
+  pub fn indicates_utf8(&self) -> bool {
+    self.general_purpose_flags & 0b0000100000000000 != 0
+  }
+
+  pub fn decoded_file_name(&self) -> String {
+    self.decoded_file_name_with(FileNameEncoding::default())
+  }
+
+  pub fn decoded_file_name_with(&self, encoding: FileNameEncoding) -> String {
+    if self.indicates_utf8() {
+      FileNameEncoding::Utf8.decode(self.file_name)
+    } else {
+      encoding.decode(self.file_name)
+    }
+  }
+
+  pub fn unicode_path(&self) -> Option<UnicodePathExtraField> {
+    extra_field::find(self.extra_field, UnicodePathExtraField::HEADER_ID).and_then(UnicodePathExtraField::parse)
+  }
+
+  pub fn effective_file_name(&self) -> String {
+    self.effective_file_name_with(FileNameEncoding::default())
+  }
+
+  pub fn effective_file_name_with(&self, encoding: FileNameEncoding) -> String {
+    match self.unicode_path() {
+      Some(unicode_path) if unicode_path.applies_to(self.file_name) => String::from_utf8_lossy(&unicode_path.unicode_name).into_owned(),
+      _ => self.decoded_file_name_with(encoding),
+    }
+  }
+
+  pub fn indicates_data_descriptor_is_present(&self) -> bool {
+    self.general_purpose_flags & 0b00001000 != 0
+  }
+
+  pub fn indicates_lzma_end_of_stream_marker(&self) -> bool {
+    self.general_purpose_flags & 0b00000010 != 0
+  }
+
+  pub fn indicates_zip64(&self) -> bool {
+    self.zip64_extended_information().is_some()
+  }
+
+  pub fn zip64_extended_information(&self) -> Option<Zip64ExtendedInformation> {
+    extra_field::find(self.extra_field, Zip64ExtendedInformation::HEADER_ID).map(|data| Zip64ExtendedInformation::parse(data, true, true, false, false))
+  }
+
+  pub fn effective_compressed_size(&self) -> u64 {
+    match self.zip64_extended_information() {
+      Some(Zip64ExtendedInformation {
+        compressed_size: Some(compressed_size),
+        ..
+      }) if self.compressed_size == u32::MAX => compressed_size,
+      _ => self.compressed_size.into(),
+    }
+  }
+}
+
+impl From<LocalFileHeaderRef<'_>> for LocalFileHeader {
+  fn from(local_file_header: LocalFileHeaderRef<'_>) -> Self {
+    let LocalFileHeaderRef {
+      signature,
+      version_needed_to_extract,
+      general_purpose_flags,
+      compression_method,
+      file_last_modification_time,
+      file_last_modification_date,
+      crc32_of_uncompressed_data,
+      compressed_size,
+      uncompressed_size,
+      file_name_length,
+      extra_field_length,
+      file_name,
+      extra_field,
+    } = local_file_header;
+
+    Self {
+      signature,
+      version_needed_to_extract,
+      general_purpose_flags,
+      compression_method,
+      file_last_modification_time,
+      file_last_modification_date,
+      crc32_of_uncompressed_data,
+      compressed_size,
+      uncompressed_size,
+      file_name_length,
+      extra_field_length,
+      file_name: file_name.to_vec(),
+      extra_field: extra_field.to_vec(),
+    }
+  }
+}
@@ -0,0 +1,118 @@
+use crate::prelude::*;
+use lzma_rs::decompress::{Options, Stream, UnpackedSize};
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LzmaHeader {
+  pub major_version: u8,
+  pub minor_version: u8,
+  pub properties_size: u16,
+  pub properties: Vec<u8>,
+}
+
+impl ExpectedSize for LzmaHeader {
+  fn expected_size(&self) -> u64 {
+    4 + u64::from(self.properties_size)
+  }
+}
+
+impl LzmaHeader {
+  pub const PROPERTIES_SIZE: u16 = 5;
+
+  pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+    let major_version = reader.read_u8()?;
+    let minor_version = reader.read_u8()?;
+    let properties_size = reader.read_u16::<LittleEndian>()?;
+
+    if properties_size != Self::PROPERTIES_SIZE {
+      #[cfg(feature = "logging")]
+      log::error!("read properties_size={properties_size} != {}", Self::PROPERTIES_SIZE);
+      return Err(Error::InvalidLzmaProperties(properties_size));
+    }
+
+    let properties = reader.read_vec(properties_size.into())?;
+
+    let value = Self {
+      major_version,
+      minor_version,
+      properties_size,
+      properties,
+    };
+
+    Ok(value)
+  }
+
+  pub fn dictionary_size(&self) -> u32 {
+    u32::from_le_bytes([self.properties[1], self.properties[2], self.properties[3], self.properties[4]])
+  }
+}
+
+pub(crate) struct LzmaDecoder<R> {
+  reader: R,
+  stream: Option<Stream<Vec<u8>>>,
+  output: Vec<u8>,
+  position: usize,
+}
+
+impl<R: Read> LzmaDecoder<R> {
+  pub(crate) fn new(mut reader: R, uncompressed_size: Option<u64>) -> Result<Self> {
+    let header = LzmaHeader::read(&mut reader)?;
+
+    let options = Options {
+      unpacked_size: UnpackedSize::UseProvided(uncompressed_size),
+      ..Default::default()
+    };
+    let mut stream = Stream::new_with_options(&options, Vec::new());
+    stream.write_all(&header.properties)?;
+
+    let value = Self {
+      reader,
+      stream: Some(stream),
+      output: Vec::new(),
+      position: 0,
+    };
+
+    Ok(value)
+  }
+
+  fn decompress_more(&mut self) -> std::io::Result<()> {
+    let Some(stream) = self.stream.as_mut() else {
+      return Ok(());
+    };
+
+    let mut input = vec![0u8; BUFFER_SIZE];
+    let length = self.reader.read(&mut input)?;
+
+    let mut remaining = &input[..length];
+    while !remaining.is_empty() {
+      match stream.write(remaining)? {
+        0 => break,
+        written => remaining = &remaining[written..],
+      }
+    }
+
+    self.output = if length == 0 || !remaining.is_empty() {
+      let stream = self.stream.take().expect("stream is present");
+      stream.finish().map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?
+    } else {
+      std::mem::take(stream.get_output_mut().expect("stream has output"))
+    };
+    self.position = 0;
+
+    Ok(())
+  }
+}
+
+impl<R: Read> Read for LzmaDecoder<R> {
+  fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+    while self.position == self.output.len() && self.stream.is_some() {
+      self.decompress_more()?;
+    }
+
+    let length = buffer.len().min(self.output.len() - self.position);
+    buffer[..length].copy_from_slice(&self.output[self.position..self.position + length]);
+    self.position += length;
+    Ok(length)
+  }
+}
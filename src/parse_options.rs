@@ -0,0 +1,85 @@
+use super::{FileNameEncoding, UnicodeCommentExtraField, UnicodePathExtraField};
+use crate::extra_field;
+use crate::prelude::*;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Strictness {
+  #[default]
+  Strict,
+  Lenient,
+}
+
+#[derive(Debug)]
+pub struct Diagnostic {
+  pub offset: u64,
+  pub error: Error,
+}
+
+#[derive(Debug, Default)]
+pub struct ParseOptions {
+  pub strictness: Strictness,
+  pub expected_file_name_encoding: FileNameEncoding,
+  pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ParseOptions {
+  pub fn strict() -> Self {
+    Self::default()
+  }
+
+  pub fn lenient() -> Self {
+    Self {
+      strictness: Strictness::Lenient,
+      ..Default::default()
+    }
+  }
+
+  pub fn is_lenient(&self) -> bool {
+    self.strictness == Strictness::Lenient
+  }
+
+  pub(crate) fn recover(&mut self, offset: u64, error: Error) -> Result<()> {
+    match self.strictness {
+      Strictness::Strict => Err(error),
+      Strictness::Lenient => {
+        self.warn(offset, error);
+        Ok(())
+      }
+    }
+  }
+
+  pub(crate) fn check_file_name(&mut self, offset: u64, file_name: &[u8], extra_field: &[u8], general_purpose_flags: u16) -> Result<()> {
+    // A stale Unicode Path is simply ignored, as APPNOTE 4.6.9 asks, so it's never fatal.
+    match extra_field::find(extra_field, UnicodePathExtraField::HEADER_ID).and_then(UnicodePathExtraField::parse) {
+      Some(unicode_path) if unicode_path.applies_to(file_name) => return Ok(()),
+      Some(_) => self.warn(offset, Error::MismatchedUnicodePath),
+      None => {}
+    }
+
+    let encoding = if general_purpose_flags & 0b0000100000000000 != 0 {
+      FileNameEncoding::Utf8
+    } else {
+      self.expected_file_name_encoding
+    };
+
+    if !encoding.can_decode(file_name) {
+      self.recover(offset, Error::UndecodableFileName(encoding))?;
+    }
+
+    Ok(())
+  }
+
+  pub(crate) fn check_file_comment(&mut self, offset: u64, file_comment: &[u8], extra_field: &[u8]) {
+    if let Some(unicode_comment) = extra_field::find(extra_field, UnicodeCommentExtraField::HEADER_ID).and_then(UnicodeCommentExtraField::parse) {
+      if !unicode_comment.applies_to(file_comment) {
+        self.warn(offset, Error::MismatchedUnicodeComment);
+      }
+    }
+  }
+
+  pub(crate) fn warn(&mut self, offset: u64, error: Error) {
+    #[cfg(feature = "logging")]
+    log::warn!("at {offset:#010X}: {error}");
+    self.diagnostics.push(Diagnostic { offset, error });
+  }
+}
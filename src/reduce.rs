@@ -0,0 +1,75 @@
+use crate::bit_reader::BitReader;
+use crate::prelude::*;
+
+const DLE: u8 = 144;
+
+pub(crate) fn unreduce(compressed: &[u8], compression_factor: u16, uncompressed_size: usize) -> Result<Vec<u8>> {
+  let invalid = || Error::InvalidCompressedData(compression_factor + 1);
+  let length_mask: u8 = 0xFF >> compression_factor;
+  let distance_shift = 8 - compression_factor;
+
+  let mut reader = BitReader::new(compressed);
+  let mut output = Vec::new();
+
+  let mut follower_sets = vec![Vec::new(); 256];
+  for follower_set in follower_sets.iter_mut().rev() {
+    let length = reader.read(6).ok_or_else(invalid)?;
+    if length > 32 {
+      return Err(invalid());
+    }
+    for _ in 0..length {
+      follower_set.push(reader.read(8).ok_or_else(invalid)? as u8);
+    }
+  }
+
+  let mut last = 0u8;
+  let mut state = 0;
+  let mut length_and_distance = 0u8;
+  let mut length = 0usize;
+
+  while output.len() < uncompressed_size {
+    let follower_set: &Vec<u8> = &follower_sets[usize::from(last)];
+    let byte = if follower_set.is_empty() || reader.read(1) == Some(1) {
+      reader.read(8).map(|byte| byte as u8)
+    } else {
+      let index_bits = usize::BITS - (follower_set.len() - 1).leading_zeros();
+      reader
+        .read(index_bits.max(1))
+        .map(|index| follower_set.get(index as usize).copied().ok_or_else(invalid))
+        .transpose()?
+    };
+    let Some(byte) = byte else {
+      break;
+    };
+    last = byte;
+
+    match state {
+      0 if byte == DLE => state = 1,
+      0 => output.push(byte),
+      1 if byte == 0 => {
+        output.push(DLE);
+        state = 0;
+      }
+      1 => {
+        length_and_distance = byte;
+        length = usize::from(byte & length_mask);
+        state = if byte & length_mask == length_mask { 2 } else { 3 };
+      }
+      2 => {
+        length += usize::from(byte);
+        state = 3;
+      }
+      _ => {
+        let distance = (usize::from(length_and_distance >> distance_shift) << 8) + usize::from(byte) + 1;
+        for _ in 0..length + 3 {
+          output.push(output.len().checked_sub(distance).map_or(0, |position| output[position]));
+        }
+        state = 0;
+      }
+    }
+  }
+
+  output.truncate(uncompressed_size);
+
+  Ok(output)
+}
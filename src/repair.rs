@@ -0,0 +1,126 @@
+use super::{CentralDirectory, CentralDirectoryFileHeader, EndOfCentralDirectory, Entry, Incomplete, Offsets, ParseOptions, Scan, Stop};
+use crate::prelude::*;
+use std::io::SeekFrom;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Repair {
+  pub prefix: Vec<u8>,
+  pub entries: Vec<Entry>,
+  pub central_directory: CentralDirectory,
+  pub unmatched: Vec<CentralDirectoryFileHeader>,
+}
+
+impl Repair {
+  pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
+    let scan = Scan::read_partial_with_options(reader, Incomplete::Include, options)?;
+    let stop_position = reader.stream_position()?;
+    let existing = Self::read_existing(reader, stop_position, &scan, options)?;
+
+    let prefix_length = scan.central_directory.prefix_length;
+    reader.seek(SeekFrom::Start(0))?;
+    let prefix = reader.read_vec(prefix_length.try_into()?)?;
+
+    let mut unmatched = existing.files;
+    let mut central_directory = CentralDirectory::new();
+    central_directory.prefix_length = prefix_length;
+
+    for (entry, scanned) in scan.entries.iter().zip(&scan.central_directory.files) {
+      central_directory.add(entry)?;
+      if let Some(file) = central_directory.files.last_mut() {
+        if let Some(index) = Self::find_match(&unmatched, existing.prefix_length, scanned, prefix_length) {
+          let original = unmatched.remove(index);
+          file.inherit_metadata_from(&original)?;
+        } else {
+          #[cfg(feature = "logging")]
+          log::debug!("no existing Central Directory File Header for {:?}", file.decoded_file_name());
+        }
+      }
+    }
+
+    #[cfg(feature = "logging")]
+    for file in &unmatched {
+      log::warn!("dropping Central Directory File Header for {:?}: no matching local entry", file.decoded_file_name());
+    }
+
+    central_directory.end.comment_length = existing.end.comment_length;
+    central_directory.end.comment = existing.end.comment;
+    central_directory.set_offset_of_start_of_central_directory(central_directory.offset_of_start_of_central_directory())?;
+
+    Ok(Self {
+      prefix,
+      entries: scan.entries,
+      central_directory,
+      unmatched,
+    })
+  }
+
+  pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
+    self.write_with_offsets(writer, Offsets::Relative)
+  }
+
+  pub fn write_with_offsets<W: Write + Seek>(&self, writer: &mut W, offsets: Offsets) -> Result<()> {
+    if offsets == Offsets::Absolute {
+      writer.write_all(&self.prefix)?;
+    }
+    for entry in &self.entries {
+      entry.write(writer)?;
+    }
+    self.central_directory.write_with_offsets(writer, offsets)
+  }
+
+  fn read_existing<R: Read + Seek>(reader: &mut R, stop_position: u64, scan: &Scan, options: &mut ParseOptions) -> Result<CentralDirectory> {
+    match CentralDirectory::read_from_end_with_options(reader, options) {
+      Ok(central_directory) => return Ok(central_directory),
+      Err(_error) => {
+        #[cfg(feature = "logging")]
+        log::warn!("existing Central Directory is unreadable: {_error}");
+      }
+    }
+
+    let end = match EndOfCentralDirectory::find(reader) {
+      Ok(position) => {
+        reader.seek(SeekFrom::Start(position))?;
+        EndOfCentralDirectory::read(reader).unwrap_or_default()
+      }
+      Err(_) => EndOfCentralDirectory::default(),
+    };
+
+    let mut files = Vec::new();
+    if scan.stop == Stop::Signature(CentralDirectoryFileHeader::SIGNATURE) {
+      reader.seek(SeekFrom::Start(stop_position))?;
+      while let Ok(file) = CentralDirectoryFileHeader::read(reader) {
+        files.push(file);
+      }
+    }
+
+    #[cfg(feature = "logging")]
+    log::debug!("recovered {} Central Directory File Headers after the last local entry", files.len());
+
+    Ok(CentralDirectory {
+      files,
+      zip64_end: None,
+      end,
+      prefix_length: scan.central_directory.prefix_length,
+    })
+  }
+
+  fn find_match(existing: &[CentralDirectoryFileHeader], existing_prefix_length: u64, scanned: &CentralDirectoryFileHeader, scanned_prefix_length: u64) -> Option<usize> {
+    let matches = |file: &CentralDirectoryFileHeader| {
+      file.file_name == scanned.file_name
+        && file.crc32_of_uncompressed_data == scanned.crc32_of_uncompressed_data
+        && file.effective_compressed_size() == scanned.effective_compressed_size()
+    };
+    let same_offset = |file: &CentralDirectoryFileHeader| {
+      file.effective_relative_offset_of_local_file_header() + existing_prefix_length == scanned.effective_relative_offset_of_local_file_header() + scanned_prefix_length
+    };
+
+    existing
+      .iter()
+      .position(|file| matches(file) && same_offset(file))
+      .or_else(|| existing.iter().position(matches))
+  }
+}
@@ -0,0 +1,173 @@
+use super::{CentralDirectory, Entry, FileNameEncoding, Incomplete, LazyEntry, LocalEntry, LocalFileHeader, ParseOptions};
+use crate::prelude::*;
+use crate::search::Search;
+use std::io::SeekFrom;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stop {
+  EndOfFile,
+  Truncated { remaining: usize },
+  Signature([u8; 4]),
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Scan<E = Entry> {
+  pub entries: Vec<E>,
+  pub central_directory: CentralDirectory,
+  pub stop: Stop,
+}
+
+enum Next<E> {
+  Entry(E),
+  Skipped,
+  Truncated,
+}
+
+impl Scan<Entry> {
+  pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
+    Self::read_with(reader, |reader| Entry::read_with_options(reader, options).map(Next::Entry))
+  }
+
+  pub fn read_partial<R: Read + Seek>(reader: &mut R, incomplete: Incomplete) -> Result<Self> {
+    Self::read_partial_with_options(reader, incomplete, &mut ParseOptions::default())
+  }
+
+  pub fn read_partial_with_options<R: Read + Seek>(reader: &mut R, incomplete: Incomplete, options: &mut ParseOptions) -> Result<Self> {
+    Self::read_with(reader, |reader| {
+      let entry = match Entry::read_partial_with_options(reader, options) {
+        Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(Next::Truncated),
+        result => result?,
+      };
+      match (entry.incomplete, incomplete) {
+        (false, _) => Ok(Next::Entry(entry)),
+        (true, Incomplete::Include) => Ok(Next::Entry(entry.completed()?)),
+        (true, Incomplete::Exclude) if Self::is_at_end(reader)? => Ok(Next::Truncated),
+        (true, Incomplete::Exclude) => Ok(Next::Skipped),
+      }
+    })
+  }
+
+  pub fn transcode_file_names(&mut self, encoding: FileNameEncoding) -> Result<()> {
+    let encoding = match encoding {
+      FileNameEncoding::Auto => {
+        let legacy = self
+          .entries
+          .iter()
+          .map(|entry| &entry.header)
+          .filter(|header| !header.indicates_utf8() && !header.unicode_path().is_some_and(|unicode_path| unicode_path.applies_to(&header.file_name)));
+        FileNameEncoding::detect(legacy.map(|header| header.file_name.as_slice())).encoding
+      }
+      encoding => encoding,
+    };
+
+    // Longer names move every later entry, so the Central Directory is rebuilt rather than transcoded in place.
+    let mut central_directory = CentralDirectory::new();
+    central_directory.prefix_length = self.central_directory.prefix_length;
+    for entry in &mut self.entries {
+      entry.header.transcode_to_utf8(encoding)?;
+      central_directory.add(entry)?;
+    }
+    self.central_directory = central_directory;
+    Ok(())
+  }
+
+  fn is_at_end<R: Read + Seek>(reader: &mut R) -> Result<bool> {
+    let stream_position = reader.stream_position()?;
+    let length = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(stream_position))?;
+    Ok(stream_position == length)
+  }
+}
+
+impl Scan<LazyEntry> {
+  pub fn read_lazy<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    Self::read_lazy_with_options(reader, &mut ParseOptions::default())
+  }
+
+  pub fn read_lazy_with_options<R: Read + Seek>(reader: &mut R, options: &mut ParseOptions) -> Result<Self> {
+    Self::read_with(reader, |reader| LazyEntry::read_with_options(reader, options).map(Next::Entry))
+  }
+}
+
+impl<E: LocalEntry> Scan<E> {
+  fn read_with<R: Read + Seek>(reader: &mut R, mut read_entry: impl FnMut(&mut R) -> Result<Next<E>>) -> Result<Self> {
+    let mut entries = Vec::new();
+    let mut central_directory = CentralDirectory::new();
+
+    let prefix_length = match Self::peek_signature(reader)? {
+      Ok(signature) if signature == LocalFileHeader::SIGNATURE => reader.stream_position()?,
+      _ => match reader.search(&LocalFileHeader::SIGNATURE)? {
+        Some(offset) => {
+          #[cfg(feature = "logging")]
+          log::debug!("skipping {offset} bytes of prepended data");
+          offset
+        }
+        None => reader.stream_position()?,
+      },
+    };
+    central_directory.prefix_length = prefix_length;
+
+    let stop = loop {
+      let offset = reader.stream_position()?;
+
+      match Self::peek_signature(reader)? {
+        Ok(signature) if signature == LocalFileHeader::SIGNATURE => (),
+        Ok(signature) => break Stop::Signature(signature),
+        Err(stop) => break stop,
+      }
+
+      match read_entry(reader)? {
+        Next::Entry(entry) => {
+          // The Central Directory describes the returned entries written back-to-back, which skipped or
+          // completed entries would otherwise throw off.
+          central_directory.add(&entry)?;
+          entries.push(entry);
+        }
+        Next::Skipped => {
+          #[cfg(feature = "logging")]
+          log::debug!("skipping incomplete entry at {offset:#010X}");
+        }
+        Next::Truncated => {
+          let remaining = reader.seek(SeekFrom::End(0))? - offset;
+          reader.seek(SeekFrom::Start(offset))?;
+          break Stop::Truncated {
+            remaining: remaining.try_into()?,
+          };
+        }
+      }
+    };
+
+    #[cfg(feature = "logging")]
+    log::debug!("scan stopped after {} entries: {stop:?}", entries.len());
+
+    Ok(Self {
+      entries,
+      central_directory,
+      stop,
+    })
+  }
+
+  fn peek_signature<R: Read + Seek>(reader: &mut R) -> Result<std::result::Result<[u8; 4], Stop>> {
+    let mut signature = [0u8; 4];
+    let mut length = 0;
+    while length < signature.len() {
+      match reader.read(&mut signature[length..]) {
+        Ok(0) => break,
+        Ok(n) => length += n,
+        Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+        Err(error) => return Err(error.into()),
+      }
+    }
+    reader.seek(SeekFrom::Current(-i64::try_from(length)?))?;
+
+    Ok(match length {
+      0 => Err(Stop::EndOfFile),
+      4 => Ok(signature),
+      remaining => Err(Stop::Truncated { remaining }),
+    })
+  }
+}
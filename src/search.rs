@@ -0,0 +1,41 @@
+use crate::prelude::*;
+use std::io::SeekFrom;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+pub(crate) trait Search: Read + Seek {
+  fn search(&mut self, pattern: &[u8]) -> Result<Option<u64>> {
+    let initial_stream_position = self.stream_position()?;
+    let overlap = pattern.len().saturating_sub(1);
+    let mut buffer = vec![0u8; CHUNK_SIZE + overlap];
+    let mut buffer_position = initial_stream_position;
+    let mut filled = 0;
+
+    loop {
+      let length = match self.read(&mut buffer[filled..]) {
+        Ok(length) => length,
+        Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+        Err(error) => return Err(error.into()),
+      };
+      filled += length;
+
+      if let Some(index) = buffer[..filled].windows(pattern.len()).position(|window| window == pattern) {
+        let position = buffer_position + u64::try_from(index)?;
+        self.seek(SeekFrom::Start(position))?;
+        return Ok(Some(position));
+      }
+
+      if length == 0 {
+        self.seek(SeekFrom::Start(initial_stream_position))?;
+        return Ok(None);
+      }
+
+      let keep = overlap.min(filled);
+      buffer.copy_within(filled - keep..filled, 0);
+      buffer_position += u64::try_from(filled - keep)?;
+      filled = keep;
+    }
+  }
+}
+
+impl<T> Search for T where T: Read + Seek {}
@@ -0,0 +1,116 @@
+use crate::bit_reader::BitReader;
+use crate::prelude::*;
+
+const MINIMUM_CODE_SIZE: u32 = 9;
+const MAXIMUM_CODE_SIZE: u32 = 13;
+const MAXIMUM_CODE: usize = (1 << MAXIMUM_CODE_SIZE) - 1;
+const CONTROL_CODE: usize = 256;
+const INCREASE_CODE_SIZE: u32 = 1;
+const PARTIAL_CLEAR: u32 = 2;
+
+#[derive(Clone, Copy)]
+struct Code {
+  prefix: Option<usize>,
+  byte: u8,
+}
+
+// Codes are stored as their prefix code and last byte and are expanded by walking the prefix chain,
+// so a code whose prefix was cleared and reassigned takes on the meaning of the new prefix.
+pub(crate) fn unshrink(compressed: &[u8], uncompressed_size: Option<usize>) -> Result<Vec<u8>> {
+  let invalid = || Error::InvalidCompressedData(1);
+  let unused = Code { prefix: None, byte: 0 };
+
+  let mut reader = BitReader::new(compressed);
+  let mut output = Vec::new();
+  let mut codes = [unused; MAXIMUM_CODE + 1];
+  let mut free: Vec<usize> = (CONTROL_CODE + 1..=MAXIMUM_CODE).rev().collect();
+  let mut code_size = MINIMUM_CODE_SIZE;
+
+  let Some(first) = reader.read(code_size) else {
+    return Ok(output);
+  };
+  let mut previous = usize::try_from(first)?;
+  if previous >= CONTROL_CODE {
+    return Err(invalid());
+  }
+  output.push(previous as u8);
+
+  while uncompressed_size.is_none_or(|size| output.len() < size) {
+    let Some(code) = reader.read(code_size) else {
+      break;
+    };
+    let code = usize::try_from(code)?;
+
+    if code == CONTROL_CODE {
+      match reader.read(code_size) {
+        Some(INCREASE_CODE_SIZE) if code_size < MAXIMUM_CODE_SIZE => code_size += 1,
+        Some(PARTIAL_CLEAR) => free = partial_clear(&mut codes),
+        Some(_) => return Err(invalid()),
+        None => break,
+      }
+      continue;
+    }
+
+    let is_defined = |code: usize| code < CONTROL_CODE || codes[code].prefix.is_some();
+    let first = if is_defined(code) {
+      expand(&codes, code, &mut output).ok_or_else(invalid)?
+    } else if free.last() == Some(&code) && is_defined(previous) {
+      let first = expand(&codes, previous, &mut output).ok_or_else(invalid)?;
+      output.push(first);
+      first
+    } else {
+      return Err(invalid());
+    };
+
+    if let Some(new) = free.pop() {
+      codes[new] = Code {
+        prefix: Some(previous),
+        byte: first,
+      };
+    }
+    previous = code;
+  }
+
+  if let Some(size) = uncompressed_size {
+    output.truncate(size);
+  }
+
+  Ok(output)
+}
+
+// Appends the bytes of `code` to `output` and returns the first of them.
+fn expand(codes: &[Code], mut code: usize, output: &mut Vec<u8>) -> Option<u8> {
+  let start = output.len();
+  while code > CONTROL_CODE {
+    if output.len() - start > MAXIMUM_CODE {
+      return None;
+    }
+    let Code { prefix, byte } = codes[code];
+    output.push(byte);
+    code = prefix?;
+  }
+  if code == CONTROL_CODE {
+    return None;
+  }
+  output.push(code as u8);
+  output[start..].reverse();
+  Some(output[start])
+}
+
+fn partial_clear(codes: &mut [Code]) -> Vec<usize> {
+  let mut is_prefix = [false; MAXIMUM_CODE + 1];
+  for code in &codes[CONTROL_CODE + 1..] {
+    if let Some(prefix) = code.prefix {
+      is_prefix[prefix] = true;
+    }
+  }
+
+  let mut free = Vec::new();
+  for code in (CONTROL_CODE + 1..=MAXIMUM_CODE).rev() {
+    if !is_prefix[code] {
+      codes[code].prefix = None;
+      free.push(code);
+    }
+  }
+  free
+}
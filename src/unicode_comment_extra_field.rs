@@ -0,0 +1,45 @@
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnicodeCommentExtraField {
+  pub version: u8,
+  pub comment_crc32: u32,
+  pub unicode_comment: Vec<u8>,
+}
+
+impl UnicodeCommentExtraField {
+  pub const HEADER_ID: u16 = 0x6375;
+  pub const VERSION: u8 = 1;
+
+  pub fn new(file_comment: &[u8], unicode_comment: &str) -> Self {
+    Self {
+      version: Self::VERSION,
+      comment_crc32: crc32fast::hash(file_comment),
+      unicode_comment: unicode_comment.as_bytes().to_vec(),
+    }
+  }
+
+  pub fn parse(data: &[u8]) -> Option<Self> {
+    let mut reader = data;
+    let version = reader.read_u8().ok()?;
+    let comment_crc32 = reader.read_u32::<LittleEndian>().ok()?;
+
+    Some(Self {
+      version,
+      comment_crc32,
+      unicode_comment: reader.to_vec(),
+    })
+  }
+
+  pub fn applies_to(&self, file_comment: &[u8]) -> bool {
+    self.version == Self::VERSION && self.comment_crc32 == crc32fast::hash(file_comment) && std::str::from_utf8(&self.unicode_comment).is_ok()
+  }
+
+  pub fn to_bytes(&self) -> Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(5 + self.unicode_comment.len());
+    data.write_u8(self.version)?;
+    data.write_u32::<LittleEndian>(self.comment_crc32)?;
+    data.write_all(&self.unicode_comment)?;
+    Ok(data)
+  }
+}
@@ -0,0 +1,45 @@
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnicodePathExtraField {
+  pub version: u8,
+  pub name_crc32: u32,
+  pub unicode_name: Vec<u8>,
+}
+
+impl UnicodePathExtraField {
+  pub const HEADER_ID: u16 = 0x7075;
+  pub const VERSION: u8 = 1;
+
+  pub fn new(file_name: &[u8], unicode_name: &str) -> Self {
+    Self {
+      version: Self::VERSION,
+      name_crc32: crc32fast::hash(file_name),
+      unicode_name: unicode_name.as_bytes().to_vec(),
+    }
+  }
+
+  pub fn parse(data: &[u8]) -> Option<Self> {
+    let mut reader = data;
+    let version = reader.read_u8().ok()?;
+    let name_crc32 = reader.read_u32::<LittleEndian>().ok()?;
+
+    Some(Self {
+      version,
+      name_crc32,
+      unicode_name: reader.to_vec(),
+    })
+  }
+
+  pub fn applies_to(&self, file_name: &[u8]) -> bool {
+    self.version == Self::VERSION && self.name_crc32 == crc32fast::hash(file_name) && std::str::from_utf8(&self.unicode_name).is_ok()
+  }
+
+  pub fn to_bytes(&self) -> Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(5 + self.unicode_name.len());
+    data.write_u8(self.version)?;
+    data.write_u32::<LittleEndian>(self.name_crc32)?;
+    data.write_all(&self.unicode_name)?;
+    Ok(data)
+  }
+}
@@ -0,0 +1,7 @@
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Verification {
+  #[default]
+  Strict,
+  Warn,
+  Skip,
+}
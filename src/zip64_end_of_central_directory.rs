@@ -0,0 +1,134 @@
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Zip64EndOfCentralDirectory {
+  pub signature: [u8; 4],
+  pub size_of_zip64_end_of_central_directory_record: u64,
+  pub version_made_by: u16,
+  pub version_needed_to_extract: u16,
+  pub number_of_this_disk: u32,
+  pub disk_where_central_directory_starts: u32,
+  pub number_of_central_directory_records_on_this_disk: u64,
+  pub total_number_of_central_directory_records: u64,
+  pub size_of_central_directory: u64,
+  pub offset_of_start_of_central_directory_relative_to_start_of_archive: u64,
+  pub extensible_data: Vec<u8>,
+}
+
+impl Default for Zip64EndOfCentralDirectory {
+  fn default() -> Self {
+    Self {
+      signature: Self::SIGNATURE,
+      size_of_zip64_end_of_central_directory_record: 44,
+      version_made_by: 45,
+      version_needed_to_extract: 45,
+      number_of_this_disk: 0,
+      disk_where_central_directory_starts: 0,
+      number_of_central_directory_records_on_this_disk: 0,
+      total_number_of_central_directory_records: 0,
+      size_of_central_directory: 0,
+      offset_of_start_of_central_directory_relative_to_start_of_archive: 0,
+      extensible_data: Vec::new(),
+    }
+  }
+}
+
+impl ExpectedSize for Zip64EndOfCentralDirectory {
+  fn expected_size(&self) -> u64 {
+    12 + self.size_of_zip64_end_of_central_directory_record
+  }
+}
+
+impl Zip64EndOfCentralDirectory {
+  pub const SIGNATURE: [u8; 4] = [0x50, 0x4B, 0x06, 0x06];
+
+  pub fn has_valid_signature(&self) -> bool {
+    self.signature == Self::SIGNATURE
+  }
+
+  pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    reader.trace(|reader| {
+      let mut fixed = [0u8; 56];
+      reader.read_exact(&mut fixed)?;
+      let mut fields = fixed.as_slice();
+
+      let mut signature = [0u8; 4];
+      fields.read_exact(&mut signature)?;
+
+      if signature != Self::SIGNATURE {
+        #[cfg(feature = "logging")]
+        log::error!("read signature={signature:?} != {:?}", Self::SIGNATURE);
+        return Err(Error::BadSignatureInZip64EndOfCentralDirectoryRecord);
+      }
+
+      let size_of_zip64_end_of_central_directory_record = fields.read_u64::<LittleEndian>()?;
+      let version_made_by = fields.read_u16::<LittleEndian>()?;
+      let version_needed_to_extract = fields.read_u16::<LittleEndian>()?;
+      let number_of_this_disk = fields.read_u32::<LittleEndian>()?;
+      let disk_where_central_directory_starts = fields.read_u32::<LittleEndian>()?;
+      let number_of_central_directory_records_on_this_disk = fields.read_u64::<LittleEndian>()?;
+      let total_number_of_central_directory_records = fields.read_u64::<LittleEndian>()?;
+      let size_of_central_directory = fields.read_u64::<LittleEndian>()?;
+      let offset_of_start_of_central_directory_relative_to_start_of_archive = fields.read_u64::<LittleEndian>()?;
+
+      let extensible_data_length = size_of_zip64_end_of_central_directory_record.saturating_sub(44);
+      let mut extensible_data = Vec::new();
+      reader.by_ref().take(extensible_data_length).read_to_end(&mut extensible_data)?;
+      if u64::try_from(extensible_data.len())? != extensible_data_length {
+        return Err(Error::unexpected_eof());
+      }
+
+      let value = Self {
+        signature,
+        size_of_zip64_end_of_central_directory_record,
+        version_made_by,
+        version_needed_to_extract,
+        number_of_this_disk,
+        disk_where_central_directory_starts,
+        number_of_central_directory_records_on_this_disk,
+        total_number_of_central_directory_records,
+        size_of_central_directory,
+        offset_of_start_of_central_directory_relative_to_start_of_archive,
+        extensible_data,
+      };
+
+      Ok(value)
+    })
+  }
+
+  pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
+    writer.trace(self.expected_size(), |writer| {
+      if !self.has_valid_signature() {
+        return Err(Error::BadSignatureInZip64EndOfCentralDirectoryRecord);
+      }
+
+      let Self {
+        signature,
+        size_of_zip64_end_of_central_directory_record,
+        version_made_by,
+        version_needed_to_extract,
+        number_of_this_disk,
+        disk_where_central_directory_starts,
+        number_of_central_directory_records_on_this_disk,
+        total_number_of_central_directory_records,
+        size_of_central_directory,
+        offset_of_start_of_central_directory_relative_to_start_of_archive,
+        extensible_data,
+      } = self;
+
+      writer.write_all(signature)?;
+      writer.write_u64::<LittleEndian>(*size_of_zip64_end_of_central_directory_record)?;
+      writer.write_u16::<LittleEndian>(*version_made_by)?;
+      writer.write_u16::<LittleEndian>(*version_needed_to_extract)?;
+      writer.write_u32::<LittleEndian>(*number_of_this_disk)?;
+      writer.write_u32::<LittleEndian>(*disk_where_central_directory_starts)?;
+      writer.write_u64::<LittleEndian>(*number_of_central_directory_records_on_this_disk)?;
+      writer.write_u64::<LittleEndian>(*total_number_of_central_directory_records)?;
+      writer.write_u64::<LittleEndian>(*size_of_central_directory)?;
+      writer.write_u64::<LittleEndian>(*offset_of_start_of_central_directory_relative_to_start_of_archive)?;
+      writer.write_all(extensible_data)?;
+
+      Ok(())
+    })
+  }
+}
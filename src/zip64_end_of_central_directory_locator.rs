@@ -0,0 +1,86 @@
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Zip64EndOfCentralDirectoryLocator {
+  pub signature: [u8; 4],
+  pub disk_where_zip64_end_of_central_directory_starts: u32,
+  pub offset_of_zip64_end_of_central_directory_record: u64,
+  pub total_number_of_disks: u32,
+}
+
+impl Default for Zip64EndOfCentralDirectoryLocator {
+  fn default() -> Self {
+    Self {
+      signature: Self::SIGNATURE,
+      disk_where_zip64_end_of_central_directory_starts: 0,
+      offset_of_zip64_end_of_central_directory_record: 0,
+      total_number_of_disks: 1,
+    }
+  }
+}
+
+impl ExpectedSize for Zip64EndOfCentralDirectoryLocator {
+  fn expected_size(&self) -> u64 {
+    20
+  }
+}
+
+impl Zip64EndOfCentralDirectoryLocator {
+  pub const SIGNATURE: [u8; 4] = [0x50, 0x4B, 0x06, 0x07];
+
+  pub fn has_valid_signature(&self) -> bool {
+    self.signature == Self::SIGNATURE
+  }
+
+  pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+    reader.trace(|reader| {
+      let mut fixed = [0u8; 20];
+      reader.read_exact(&mut fixed)?;
+      let mut fields = fixed.as_slice();
+
+      let mut signature = [0u8; 4];
+      fields.read_exact(&mut signature)?;
+
+      if signature != Self::SIGNATURE {
+        #[cfg(feature = "logging")]
+        log::error!("read signature={signature:?} != {:?}", Self::SIGNATURE);
+        return Err(Error::BadSignatureInZip64EndOfCentralDirectoryLocator);
+      }
+
+      let disk_where_zip64_end_of_central_directory_starts = fields.read_u32::<LittleEndian>()?;
+      let offset_of_zip64_end_of_central_directory_record = fields.read_u64::<LittleEndian>()?;
+      let total_number_of_disks = fields.read_u32::<LittleEndian>()?;
+
+      let value = Self {
+        signature,
+        disk_where_zip64_end_of_central_directory_starts,
+        offset_of_zip64_end_of_central_directory_record,
+        total_number_of_disks,
+      };
+
+      Ok(value)
+    })
+  }
+
+  pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
+    writer.trace(self.expected_size(), |writer| {
+      if !self.has_valid_signature() {
+        return Err(Error::BadSignatureInZip64EndOfCentralDirectoryLocator);
+      }
+
+      let Self {
+        signature,
+        disk_where_zip64_end_of_central_directory_starts,
+        offset_of_zip64_end_of_central_directory_record,
+        total_number_of_disks,
+      } = self;
+
+      writer.write_all(signature)?;
+      writer.write_u32::<LittleEndian>(*disk_where_zip64_end_of_central_directory_starts)?;
+      writer.write_u64::<LittleEndian>(*offset_of_zip64_end_of_central_directory_record)?;
+      writer.write_u32::<LittleEndian>(*total_number_of_disks)?;
+
+      Ok(())
+    })
+  }
+}
@@ -0,0 +1,61 @@
+use crate::prelude::*;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Zip64ExtendedInformation {
+  pub uncompressed_size: Option<u64>,
+  pub compressed_size: Option<u64>,
+  pub relative_offset_of_local_file_header: Option<u64>,
+  pub disk_number_where_file_starts: Option<u32>,
+}
+
+impl Zip64ExtendedInformation {
+  pub const HEADER_ID: u16 = 0x0001;
+  pub const VERSION_NEEDED_TO_EXTRACT: u16 = 45;
+
+  pub fn is_empty(&self) -> bool {
+    self.uncompressed_size.is_none()
+      && self.compressed_size.is_none()
+      && self.relative_offset_of_local_file_header.is_none()
+      && self.disk_number_where_file_starts.is_none()
+  }
+
+  pub fn parse(data: &[u8], uncompressed_size: bool, compressed_size: bool, relative_offset_of_local_file_header: bool, disk_number_where_file_starts: bool) -> Self {
+    let mut reader = data;
+    let mut read_u64 = |present: bool| if present { reader.read_u64::<LittleEndian>().ok() } else { None };
+
+    let uncompressed_size = read_u64(uncompressed_size);
+    let compressed_size = read_u64(compressed_size);
+    let relative_offset_of_local_file_header = read_u64(relative_offset_of_local_file_header);
+    let disk_number_where_file_starts = if disk_number_where_file_starts {
+      reader.read_u32::<LittleEndian>().ok()
+    } else {
+      None
+    };
+
+    Self {
+      uncompressed_size,
+      compressed_size,
+      relative_offset_of_local_file_header,
+      disk_number_where_file_starts,
+    }
+  }
+
+  pub fn to_bytes(&self) -> Result<Vec<u8>> {
+    let Self {
+      uncompressed_size,
+      compressed_size,
+      relative_offset_of_local_file_header,
+      disk_number_where_file_starts,
+    } = self;
+
+    let mut data = Vec::with_capacity(28);
+    for value in [uncompressed_size, compressed_size, relative_offset_of_local_file_header].into_iter().flatten() {
+      data.write_u64::<LittleEndian>(*value)?;
+    }
+    if let Some(disk_number_where_file_starts) = disk_number_where_file_starts {
+      data.write_u32::<LittleEndian>(*disk_number_where_file_starts)?;
+    }
+
+    Ok(data)
+  }
+}
@@ -0,0 +1,54 @@
+use std::io::Cursor;
+use synthzip::{Carve, CompressionMethod, Entry, LocalEntry};
+
+fn to_bytes(entry: &Entry) -> Vec<u8> {
+  let mut bytes = Cursor::new(Vec::new());
+  entry.write(&mut bytes).expect("failed to write entry");
+  bytes.into_inner()
+}
+
+#[test]
+fn rejects_empty_entries_with_undecodable_names() {
+  let valid = Entry::new(b"hello.txt".to_vec(), b"hello, hello, hello", CompressionMethod::Deflated).unwrap();
+
+  let mut control_characters = Entry::new(b"a".to_vec(), b"", CompressionMethod::Stored).unwrap();
+  control_characters.header.file_name = vec![0x01, 0x1B, 0x7F, 0x02];
+  control_characters.header.file_name_length = 4;
+
+  let mut invalid_utf8 = Entry::new(b"a".to_vec(), b"", CompressionMethod::Stored).unwrap();
+  invalid_utf8.header.general_purpose_flags |= 0b0000100000000000;
+  invalid_utf8.header.file_name = vec![b'x', 0xFF, 0xFE];
+  invalid_utf8.header.file_name_length = 3;
+
+  let mut blob = vec![0xAAu8; 17];
+  blob.extend(to_bytes(&control_characters));
+  blob.extend([0x55u8; 9]);
+  let offset = blob.len() as u64;
+  blob.extend(to_bytes(&valid));
+  blob.extend(to_bytes(&invalid_utf8));
+  blob.extend([0xAAu8; 5]);
+
+  let carve = Carve::read(&mut Cursor::new(blob)).unwrap();
+
+  assert_eq!(carve.entries.len(), 1);
+  assert_eq!(carve.entries[0].offset, offset);
+  assert_eq!(carve.entries[0].entry.header.file_name, b"hello.txt");
+}
+
+#[test]
+fn rejects_headers_whose_sizes_overflow() {
+  let valid = Entry::new(b"hello.txt".to_vec(), b"hello, hello, hello", CompressionMethod::Deflated).unwrap();
+  let mut hostile = Entry::new(b"hostile.txt".to_vec(), b"abc", CompressionMethod::Stored).unwrap();
+  hostile.header.set_sizes(u64::MAX - 5, 3).unwrap();
+  assert_eq!(hostile.size_in_archive(), u64::MAX);
+
+  let mut blob = vec![0xAAu8; 11];
+  blob.extend(to_bytes(&hostile));
+  let offset = blob.len() as u64;
+  blob.extend(to_bytes(&valid));
+
+  let carve = Carve::read(&mut Cursor::new(blob)).unwrap();
+
+  assert_eq!(carve.entries.len(), 1);
+  assert_eq!(carve.entries[0].offset, offset);
+}
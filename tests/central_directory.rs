@@ -0,0 +1,102 @@
+use std::io::{Cursor, Write};
+use synthzip::{CentralDirectory, CompressionMethod, EndOfCentralDirectory, Entry, LocalEntry};
+
+fn archive(comment: &[u8]) -> (Vec<u8>, CentralDirectory) {
+  let entry = Entry::new(b"commented.txt".to_vec(), b"commented", CompressionMethod::Deflated).unwrap();
+  let mut archive = Cursor::new(Vec::new());
+  let mut central_directory = CentralDirectory::new();
+  entry.write(&mut archive).unwrap();
+  central_directory.add(&entry).unwrap();
+  central_directory.end.comment = comment.to_vec();
+  central_directory.end.comment_length = comment.len() as u16;
+  central_directory.write(&mut archive).unwrap();
+  (archive.into_inner(), central_directory)
+}
+
+#[test]
+fn end_of_central_directory_is_found_before_a_comment() {
+  for comment in [&b"an archive comment"[..], &[b'x'; u16::MAX as usize]] {
+    let (bytes, central_directory) = archive(comment);
+    let mut reader = Cursor::new(&bytes);
+
+    let position = EndOfCentralDirectory::find(&mut reader).unwrap();
+    assert_eq!(position, (bytes.len() - 22 - comment.len()) as u64);
+    assert_eq!(CentralDirectory::read_from_end(&mut reader).unwrap(), central_directory);
+  }
+}
+
+#[test]
+fn signature_inside_the_comment_is_not_mistaken_for_the_record() {
+  // The decoy's comment length doesn't fit the remaining bytes, so only the real record is valid.
+  let comment = [&EndOfCentralDirectory::SIGNATURE[..], &[0xFF; 30]].concat();
+  let (bytes, central_directory) = archive(&comment);
+
+  assert_eq!(CentralDirectory::read_from_end(&mut Cursor::new(&bytes)).unwrap(), central_directory);
+}
+
+#[test]
+fn end_of_central_directory_is_found_before_trailing_bytes() {
+  let (mut bytes, central_directory) = archive(b"comment");
+  let length = bytes.len();
+  bytes.write_all(b"trailing garbage").unwrap();
+
+  let mut reader = Cursor::new(&bytes);
+  assert_eq!(EndOfCentralDirectory::find(&mut reader).unwrap(), (length - 22 - 7) as u64);
+  assert_eq!(CentralDirectory::read_from_end(&mut reader).unwrap(), central_directory);
+}
+
+fn entries() -> Vec<Entry> {
+  [("a.txt", &b"a"[..]), ("longer name.txt", b"longer contents"), ("c.txt", b"c c c c c c c c")]
+    .into_iter()
+    .map(|(file_name, contents)| Entry::new(file_name.as_bytes().to_vec(), contents, CompressionMethod::Deflated).unwrap())
+    .collect()
+}
+
+#[test]
+fn add_tracks_the_running_archive_offset() {
+  let mut archive = Cursor::new(Vec::new());
+  let mut central_directory = CentralDirectory::new();
+  let mut offsets = Vec::new();
+  for entry in entries() {
+    offsets.push(archive.position());
+    entry.write(&mut archive).unwrap();
+    central_directory.add(&entry).unwrap();
+  }
+
+  let recorded: Vec<u64> = central_directory
+    .files
+    .iter()
+    .map(|file| file.effective_relative_offset_of_local_file_header())
+    .collect();
+  assert_eq!(recorded, offsets);
+  assert_eq!(central_directory.offset_of_start_of_central_directory(), archive.position());
+}
+
+#[test]
+fn add_at_records_where_entries_really_are() {
+  let entries = entries();
+  let mut archive = Cursor::new(Vec::new());
+  let mut offsets = Vec::new();
+  for entry in &entries {
+    archive.write_all(b"junk").unwrap();
+    offsets.push(archive.position());
+    entry.write(&mut archive).unwrap();
+  }
+
+  // Adding the entries out of order must not move the start of the Central Directory back.
+  let mut central_directory = CentralDirectory::new();
+  for index in [2, 0, 1] {
+    central_directory.add_at(&entries[index], offsets[index]).unwrap();
+  }
+  let end_of_last_entry = offsets[2] + entries[2].size_in_archive();
+  assert_eq!(archive.position(), end_of_last_entry);
+  assert_eq!(central_directory.offset_of_start_of_central_directory(), end_of_last_entry);
+  central_directory.write(&mut archive).unwrap();
+
+  let read = CentralDirectory::read_from_end(&mut archive).unwrap();
+  assert_eq!(read, central_directory);
+  for (index, file) in [2, 0, 1].into_iter().zip(&read.files) {
+    archive.set_position(file.effective_relative_offset_of_local_file_header());
+    assert_eq!(Entry::read(&mut archive).unwrap(), entries[index]);
+  }
+}
@@ -0,0 +1,151 @@
+use std::io::{Cursor, Read, Write};
+use synthzip::{CentralDirectory, CompressionMethod, DataDescriptor, Entry, Error};
+
+#[test]
+fn compression_method_round_trips_through_its_number() {
+  for number in 0..=u16::from(u8::MAX) {
+    assert_eq!(u16::from(CompressionMethod::from(number)), number);
+  }
+  assert_eq!(CompressionMethod::from(0), CompressionMethod::Stored);
+  assert_eq!(CompressionMethod::from(8), CompressionMethod::Deflated);
+  assert_eq!(CompressionMethod::from(99), CompressionMethod::Other(99));
+}
+
+#[test]
+fn stored_data_is_passed_through() {
+  let mut decoder = CompressionMethod::Stored.decoder(&b"stored as is"[..]).unwrap();
+  let mut uncompressed = Vec::new();
+  decoder.read_to_end(&mut uncompressed).unwrap();
+  assert_eq!(uncompressed, b"stored as is");
+
+  let entry = Entry::new(b"stored.txt".to_vec(), b"stored as is", CompressionMethod::Stored).unwrap();
+  assert_eq!(entry.data, b"stored as is");
+  assert_eq!(entry.decompress().unwrap(), b"stored as is");
+}
+
+#[test]
+fn unknown_compression_method_is_reported() {
+  let mut entry = Entry::new(b"unknown.bin".to_vec(), b"opaque", CompressionMethod::Stored).unwrap();
+  entry.header.compression_method = 99;
+
+  assert!(!CompressionMethod::from(99).is_supported());
+  assert!(matches!(entry.decompress(), Err(Error::UnsupportedCompressionMethod(99))));
+  assert!(matches!(
+    Entry::new(b"unknown.bin".to_vec(), b"opaque", CompressionMethod::Other(99)),
+    Err(Error::UnsupportedCompressionMethod(99))
+  ));
+}
+
+fn deflate64_entry() -> (Entry, Vec<u8>) {
+  // Deflate64 only differs from Deflate in its window size and in what length code 285 means, so a
+  // Deflate stream without 258-byte matches is valid Deflate64 as well.
+  let contents: Vec<u8> = (0..2000).flat_map(|number: u32| format!("{number} ").into_bytes()).collect();
+  let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+  encoder.write_all(&contents).unwrap();
+
+  let mut entry = Entry::new(b"deflate64.bin".to_vec(), &contents, CompressionMethod::Stored).unwrap();
+  entry.header.compression_method = CompressionMethod::Deflate64.into();
+  entry.data = encoder.finish().unwrap();
+  entry.header.set_sizes(entry.data.len() as u64, contents.len() as u64).unwrap();
+  (entry, contents)
+}
+
+#[test]
+fn deflate64_entries_are_decompressed_and_verified() {
+  let (mut entry, contents) = deflate64_entry();
+  assert_eq!(entry.decompress().unwrap(), contents);
+
+  entry.header.crc32_of_uncompressed_data ^= 1;
+  assert!(matches!(entry.decompress(), Err(Error::ChecksumMismatch { .. })));
+}
+
+#[test]
+fn deflate64_entries_need_version_2_1() {
+  let (mut entry, _) = deflate64_entry();
+  entry.header.version_needed_to_extract = 20;
+  let mut central_directory = CentralDirectory::new();
+  central_directory.add(&entry).unwrap();
+
+  assert_eq!(CompressionMethod::Deflate64.version_needed_to_extract(), 21);
+  assert_eq!(central_directory.files[0].version_needed_to_extract, 21);
+}
+
+const CONTENTS: &[u8] = b"compressed contents, compressed contents, compressed contents";
+
+fn assert_round_trip(compression_method: CompressionMethod, version_needed_to_extract: u16) {
+  let entry = Entry::new(b"round-trip.txt".to_vec(), CONTENTS, compression_method).unwrap();
+  assert_eq!(entry.header.compression_method, u16::from(compression_method));
+  assert_eq!(entry.header.version_needed_to_extract, version_needed_to_extract);
+  assert_ne!(entry.data, CONTENTS);
+
+  let mut bytes = Cursor::new(Vec::new());
+  entry.write(&mut bytes).unwrap();
+  bytes.set_position(0);
+  let read = Entry::read(&mut bytes).unwrap();
+  assert_eq!(read, entry);
+  assert_eq!(read.decompress().unwrap(), CONTENTS);
+}
+
+fn assert_streamed_boundary_is_found_by_decoding(compression_method: CompressionMethod) {
+  let mut entry = Entry::new(b"streamed.txt".to_vec(), CONTENTS, compression_method).unwrap();
+  let data_descriptor = DataDescriptor {
+    signature: Some(DataDescriptor::SIGNATURE),
+    crc32_of_uncompressed_data: entry.header.crc32_of_uncompressed_data,
+    compressed_size: entry.data.len() as u64,
+    uncompressed_size: CONTENTS.len() as u64,
+    zip64: false,
+  };
+  entry.header.general_purpose_flags |= 0b00001000;
+  entry.header.crc32_of_uncompressed_data = 0;
+  entry.header.set_sizes(0, 0).unwrap();
+
+  let mut bytes = Cursor::new(Vec::new());
+  entry.header.write(&mut bytes).unwrap();
+  bytes.write_all(&entry.data).unwrap();
+  data_descriptor.write(&mut bytes).unwrap();
+  Entry::new(b"next.txt".to_vec(), b"next", CompressionMethod::Stored)
+    .unwrap()
+    .write(&mut bytes)
+    .unwrap();
+  bytes.set_position(0);
+
+  let read = Entry::read(&mut bytes).unwrap();
+  assert_eq!(read.data, entry.data);
+  assert_eq!(read.data_descriptor, Some(data_descriptor));
+  assert_eq!(read.decompress().unwrap(), CONTENTS);
+  assert_eq!(Entry::read(&mut bytes).unwrap().header.file_name, b"next.txt");
+}
+
+#[test]
+fn bzip2_entries_round_trip() {
+  assert_round_trip(CompressionMethod::Bzip2, 46);
+}
+
+#[test]
+fn bzip2_boundary_is_found_by_decoding() {
+  assert_streamed_boundary_is_found_by_decoding(CompressionMethod::Bzip2);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_entries_round_trip() {
+  assert_round_trip(CompressionMethod::Zstd, 63);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_boundary_is_found_by_decoding() {
+  assert_streamed_boundary_is_found_by_decoding(CompressionMethod::Zstd);
+}
+
+#[cfg(feature = "xz")]
+#[test]
+fn xz_entries_round_trip() {
+  assert_round_trip(CompressionMethod::Xz, 63);
+}
+
+#[cfg(feature = "xz")]
+#[test]
+fn xz_boundary_is_found_by_decoding() {
+  assert_streamed_boundary_is_found_by_decoding(CompressionMethod::Xz);
+}
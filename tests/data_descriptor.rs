@@ -0,0 +1,206 @@
+use std::io::Cursor;
+use synthzip::{CompressionMethod, DataDescriptor, Entry, Error, LocalEntry, ParseOptions, Scan};
+
+const CONTENTS: &[u8] = b"streamed streamed streamed streamed";
+
+fn streamed(data_descriptor_compressed_size: Option<u64>) -> Cursor<Vec<u8>> {
+  let mut entry = Entry::new(b"streamed.txt".to_vec(), CONTENTS, CompressionMethod::Deflated).unwrap();
+  let data_descriptor = DataDescriptor {
+    signature: Some(DataDescriptor::SIGNATURE),
+    crc32_of_uncompressed_data: entry.header.crc32_of_uncompressed_data,
+    compressed_size: entry.data.len() as u64,
+    uncompressed_size: CONTENTS.len() as u64,
+    zip64: false,
+  };
+  entry.header.general_purpose_flags |= 0b00001000;
+  entry.header.crc32_of_uncompressed_data = 0;
+  entry.header.set_sizes(0, 0).unwrap();
+
+  let mut bytes = Cursor::new(Vec::new());
+  entry.header.write(&mut bytes).unwrap();
+  std::io::Write::write_all(&mut bytes, &entry.data).unwrap();
+  if let Some(compressed_size) = data_descriptor_compressed_size {
+    DataDescriptor {
+      compressed_size,
+      ..data_descriptor
+    }
+    .write(&mut bytes)
+    .unwrap();
+  }
+  Entry::new(b"next.txt".to_vec(), b"next", CompressionMethod::Stored)
+    .unwrap()
+    .write(&mut bytes)
+    .unwrap();
+  bytes.set_position(0);
+  bytes
+}
+
+#[test]
+fn conflicting_data_descriptor_fails_in_strict_mode() {
+  let result = Entry::read_with_options(&mut streamed(Some(999)), &mut ParseOptions::strict());
+
+  assert!(matches!(result, Err(Error::DataDescriptorConflictsWithCompressedData)));
+}
+
+#[test]
+fn conflicting_data_descriptor_is_recorded_in_lenient_mode() {
+  let mut options = ParseOptions::lenient();
+  let mut reader = streamed(Some(999));
+  let entry = Entry::read_with_options(&mut reader, &mut options).unwrap();
+
+  assert_eq!(entry.decompress().unwrap(), CONTENTS);
+  assert_eq!(
+    entry.data_descriptor.map(|data_descriptor| data_descriptor.compressed_size),
+    Some(entry.data.len() as u64)
+  );
+  assert_eq!(entry.size_in_archive(), reader.position());
+  assert_eq!(Entry::read(&mut reader).unwrap().header.file_name, b"next.txt");
+  assert!(options
+    .diagnostics
+    .iter()
+    .any(|diagnostic| matches!(diagnostic.error, Error::DataDescriptorConflictsWithCompressedData)));
+}
+
+#[test]
+fn conflicting_data_descriptor_keeps_central_directory_offsets_in_lenient_mode() {
+  let bytes = streamed(Some(999)).into_inner();
+
+  let scan = Scan::read_with_options(&mut Cursor::new(&bytes), &mut ParseOptions::lenient()).unwrap();
+  assert_eq!(scan.central_directory.offset_of_start_of_central_directory(), bytes.len() as u64);
+  assert_eq!(
+    scan.central_directory.files[1].effective_relative_offset_of_local_file_header(),
+    scan.entries[0].size_in_archive()
+  );
+
+  let lazy = Scan::read_lazy_with_options(&mut Cursor::new(&bytes), &mut ParseOptions::lenient()).unwrap();
+  assert_eq!(lazy.central_directory, scan.central_directory);
+}
+
+#[test]
+fn missing_data_descriptor_is_measured_in_strict_mode() {
+  let mut reader = streamed(None);
+  let entry = Entry::read_with_options(&mut reader, &mut ParseOptions::strict()).unwrap();
+
+  assert_eq!(entry.data_descriptor, None);
+  assert_eq!(entry.header.effective_uncompressed_size(), CONTENTS.len() as u64);
+  assert_eq!(entry.decompress().unwrap(), CONTENTS);
+  assert_eq!(Entry::read(&mut reader).unwrap().header.file_name, b"next.txt");
+}
+
+#[test]
+fn data_descriptor_between_entries_is_found_by_inflating() {
+  let compressed_size = Entry::new(b"streamed.txt".to_vec(), CONTENTS, CompressionMethod::Deflated).unwrap().data.len() as u64;
+  let mut reader = streamed(Some(compressed_size));
+
+  let entry = Entry::read(&mut reader).unwrap();
+  assert_eq!(entry.data.len() as u64, compressed_size);
+  assert_eq!(
+    entry.data_descriptor.map(|data_descriptor| data_descriptor.uncompressed_size),
+    Some(CONTENTS.len() as u64)
+  );
+  assert_eq!(entry.decompress().unwrap(), CONTENTS);
+  assert_eq!(Entry::read(&mut reader).unwrap().header.file_name, b"next.txt");
+}
+
+#[test]
+fn data_descriptor_is_found_by_searching_when_the_data_cannot_be_decoded() {
+  // An unknown compression method can't be decoded, so its data runs up to the first descriptor whose size matches.
+  let decoy = DataDescriptor {
+    signature: Some(DataDescriptor::SIGNATURE),
+    crc32_of_uncompressed_data: 0,
+    compressed_size: 3,
+    uncompressed_size: 3,
+    zip64: false,
+  };
+  let mut opaque = Cursor::new(b"opaque".to_vec());
+  opaque.set_position(6);
+  decoy.write(&mut opaque).unwrap();
+  std::io::Write::write_all(&mut opaque, b"data").unwrap();
+  let opaque = opaque.into_inner();
+
+  let mut entry = Entry::new(b"opaque.bin".to_vec(), &opaque, CompressionMethod::Stored).unwrap();
+  entry.header.compression_method = 99;
+  entry.header.general_purpose_flags |= 0b00001000;
+  entry.header.set_sizes(0, 0).unwrap();
+  let data_descriptor = DataDescriptor {
+    signature: Some(DataDescriptor::SIGNATURE),
+    crc32_of_uncompressed_data: entry.header.crc32_of_uncompressed_data,
+    compressed_size: opaque.len() as u64,
+    uncompressed_size: opaque.len() as u64,
+    zip64: false,
+  };
+
+  let mut bytes = Cursor::new(Vec::new());
+  entry.header.write(&mut bytes).unwrap();
+  std::io::Write::write_all(&mut bytes, &opaque).unwrap();
+  data_descriptor.write(&mut bytes).unwrap();
+  Entry::new(b"next.txt".to_vec(), b"next", CompressionMethod::Stored)
+    .unwrap()
+    .write(&mut bytes)
+    .unwrap();
+  bytes.set_position(0);
+
+  let read = Entry::read(&mut bytes).unwrap();
+  assert_eq!(read.data, opaque);
+  assert_eq!(read.data_descriptor, Some(data_descriptor));
+  assert_eq!(Entry::read(&mut bytes).unwrap().header.file_name, b"next.txt");
+}
+
+fn stored_streamed(contents: &[u8], signature: Option<[u8; 4]>, zip64: bool) -> Cursor<Vec<u8>> {
+  let mut entry = Entry::new(b"mimetype".to_vec(), contents, CompressionMethod::Stored).unwrap();
+  let data_descriptor = DataDescriptor {
+    signature,
+    crc32_of_uncompressed_data: entry.header.crc32_of_uncompressed_data,
+    compressed_size: contents.len() as u64,
+    uncompressed_size: contents.len() as u64,
+    zip64,
+  };
+  if zip64 {
+    entry.header.set_sizes(u32::MAX.into(), u32::MAX.into()).unwrap();
+  }
+  entry.header.general_purpose_flags |= 0b00001000;
+  entry.header.crc32_of_uncompressed_data = 0;
+  entry.header.set_sizes(0, 0).unwrap();
+
+  let mut bytes = Cursor::new(Vec::new());
+  entry.header.write(&mut bytes).unwrap();
+  std::io::Write::write_all(&mut bytes, contents).unwrap();
+  data_descriptor.write(&mut bytes).unwrap();
+  Entry::new(b"next.txt".to_vec(), b"next", CompressionMethod::Stored)
+    .unwrap()
+    .write(&mut bytes)
+    .unwrap();
+  bytes.set_position(0);
+  bytes
+}
+
+#[test]
+fn stored_data_descriptor_is_found_by_checking_the_crc() {
+  // The stored data embeds a descriptor whose size matches the distance searched but whose CRC doesn't.
+  let mut contents = b"application/epub+zip ".to_vec();
+  let decoy = DataDescriptor {
+    signature: Some(DataDescriptor::SIGNATURE),
+    crc32_of_uncompressed_data: 0xDEADBEEF,
+    compressed_size: contents.len() as u64,
+    uncompressed_size: contents.len() as u64,
+    zip64: false,
+  };
+  let mut decoy_bytes = Cursor::new(Vec::new());
+  decoy.write(&mut decoy_bytes).unwrap();
+  contents.extend(decoy_bytes.into_inner());
+  contents.extend(b" and more stored bytes");
+
+  for (signature, zip64) in [(Some(DataDescriptor::SIGNATURE), false), (None, false), (Some(DataDescriptor::SIGNATURE), true)] {
+    let mut reader = stored_streamed(&contents, signature, zip64);
+    let entry = Entry::read(&mut reader).unwrap();
+
+    assert_eq!(entry.data, contents);
+    assert_eq!(
+      entry.data_descriptor.map(|data_descriptor| (data_descriptor.signature, data_descriptor.zip64)),
+      Some((signature, zip64))
+    );
+    assert_eq!(entry.effective_header().unwrap().effective_compressed_size(), contents.len() as u64);
+    assert_eq!(entry.decompress().unwrap(), contents);
+    assert_eq!(Entry::read(&mut reader).unwrap().header.file_name, b"next.txt");
+  }
+}
@@ -0,0 +1,90 @@
+use std::io::Cursor;
+use synthzip::{Carve, CompressionMethod, Entry, EntryRef, Error, Incomplete, Repair, Scan, Verification, Zip64ExtendedInformation};
+
+fn with_implausible_uncompressed_size(uncompressed: &[u8]) -> Entry {
+  let mut entry = Entry::new(b"huge.txt".to_vec(), uncompressed, CompressionMethod::Deflated).unwrap();
+  let zip64_extended_information = Zip64ExtendedInformation {
+    uncompressed_size: Some(u64::MAX - 10),
+    compressed_size: Some(entry.data.len() as u64),
+    ..Default::default()
+  };
+  entry.header.uncompressed_size = u32::MAX;
+  entry.header.extra_field = [&[0x01, 0x00, 0x10, 0x00][..], &zip64_extended_information.to_bytes().unwrap()].concat();
+  entry.header.extra_field_length = entry.header.extra_field.len() as u16;
+  entry
+}
+
+#[test]
+fn decompress_does_not_trust_uncompressed_size_for_preallocation() {
+  let entry = with_implausible_uncompressed_size(b"abc");
+  assert_eq!(entry.header.effective_uncompressed_size(), u64::MAX - 10);
+
+  assert_eq!(entry.decompress().unwrap(), b"abc");
+}
+
+#[test]
+fn carve_survives_implausible_uncompressed_size() {
+  let entry = with_implausible_uncompressed_size(b"abc");
+  let mut blob = Cursor::new(vec![0u8; 7]);
+  blob.set_position(7);
+  entry.write(&mut blob).unwrap();
+  blob.set_position(0);
+
+  let carve = Carve::read(&mut blob).unwrap();
+
+  assert_eq!(carve.entries.len(), 1);
+  assert_eq!(carve.entries[0].entry.decompress().unwrap(), b"abc");
+}
+
+#[test]
+fn verification_policy_decides_what_a_checksum_mismatch_does() {
+  let mut entry = Entry::new(b"corrupt.txt".to_vec(), b"abc", CompressionMethod::Stored).unwrap();
+  entry.header.crc32_of_uncompressed_data ^= 1;
+  let found = crc32fast::hash(b"abc");
+
+  assert!(matches!(
+    entry.decompress(),
+    Err(Error::ChecksumMismatch { expected, found: actual }) if expected == found ^ 1 && actual == found
+  ));
+  assert_eq!(entry.decompress_with_verification(Verification::Warn).unwrap(), b"abc");
+  assert_eq!(entry.decompress_with_verification(Verification::Skip).unwrap(), b"abc");
+  assert_eq!(entry.effective_crc32_of_uncompressed_data(), found ^ 1);
+}
+
+fn with_hostile_compressed_size(compressed_size: u64) -> Vec<u8> {
+  let mut entry = Entry::new(b"hostile.txt".to_vec(), b"hostile", CompressionMethod::Stored).unwrap();
+  entry.header.set_sizes(compressed_size, 7).unwrap();
+  let mut bytes = Cursor::new(Vec::new());
+  entry.write(&mut bytes).unwrap();
+  bytes.into_inner()
+}
+
+#[test]
+fn hostile_compressed_size_is_not_preallocated() {
+  for compressed_size in [1 << 46, u64::MAX - 5] {
+    let bytes = with_hostile_compressed_size(compressed_size);
+
+    assert!(matches!(Entry::read(&mut Cursor::new(&bytes)), Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof));
+    assert!(matches!(Scan::read(&mut Cursor::new(&bytes)), Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof));
+
+    // Partial reads keep what is actually there.
+    let scan = Scan::read_partial(&mut Cursor::new(&bytes), Incomplete::Include).unwrap();
+    assert_eq!(scan.entries.len(), 1);
+    assert_eq!(scan.entries[0].data, b"hostile");
+    let repair = Repair::read(&mut Cursor::new(&bytes)).unwrap();
+    assert_eq!(repair.entries, scan.entries);
+  }
+}
+
+#[test]
+fn entry_ref_rejects_sizes_past_the_end_of_the_bytes() {
+  for compressed_size in [8, 1 << 46, u64::MAX - 5] {
+    let bytes = with_hostile_compressed_size(compressed_size);
+    assert!(matches!(EntryRef::parse(&bytes), Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof));
+  }
+
+  let bytes = with_hostile_compressed_size(7);
+  let entry = EntryRef::parse(&bytes).unwrap();
+  assert_eq!(entry.data, b"hostile");
+  assert_eq!(Entry::from(entry), Entry::read(&mut Cursor::new(&bytes)).unwrap());
+}
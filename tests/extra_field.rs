@@ -0,0 +1,102 @@
+use std::io::Cursor;
+use synthzip::{CentralDirectory, CompressionMethod, Entry, Error, FileNameEncoding, ParseOptions, UnicodeCommentExtraField, UnicodePathExtraField};
+
+#[test]
+fn zip64_record_is_placed_before_padding() {
+  let mut entry = Entry::new(b"large.bin".to_vec(), b"", CompressionMethod::Stored).unwrap();
+  entry.header.extra_field = vec![0x00, 0x00];
+  entry.header.extra_field_length = 2;
+
+  entry.header.set_sizes(u64::from(u32::MAX) + 1, u64::from(u32::MAX) + 2).unwrap();
+
+  assert_eq!(entry.header.effective_compressed_size(), u64::from(u32::MAX) + 1);
+  assert_eq!(entry.header.effective_uncompressed_size(), u64::from(u32::MAX) + 2);
+  assert!(entry.header.extra_field.ends_with(&[0x00, 0x00]));
+  assert_eq!(usize::from(entry.header.extra_field_length), entry.header.extra_field.len());
+}
+
+fn extra_field(header_id: u16, data: &[u8]) -> Vec<u8> {
+  [&header_id.to_le_bytes()[..], &(data.len() as u16).to_le_bytes(), data].concat()
+}
+
+fn with_unicode_path(file_name: &[u8], unicode_path: &UnicodePathExtraField) -> Entry {
+  let mut entry = Entry::new(file_name.to_vec(), b"contents", CompressionMethod::Stored).unwrap();
+  entry.header.general_purpose_flags &= !0b0000100000000000;
+  entry.header.extra_field = extra_field(UnicodePathExtraField::HEADER_ID, &unicode_path.to_bytes().unwrap());
+  entry.header.extra_field_length = entry.header.extra_field.len() as u16;
+  entry
+}
+
+fn archive(entry: &Entry, file_comment: &[u8], unicode_comment: Option<&UnicodeCommentExtraField>) -> Vec<u8> {
+  let mut bytes = Cursor::new(Vec::new());
+  entry.write(&mut bytes).unwrap();
+  let mut central_directory = CentralDirectory::new();
+  central_directory.add(entry).unwrap();
+  let file = &mut central_directory.files[0];
+  file.set_file_comment(file_comment.to_vec()).unwrap();
+  if let Some(unicode_comment) = unicode_comment {
+    file
+      .extra_field
+      .extend(extra_field(UnicodeCommentExtraField::HEADER_ID, &unicode_comment.to_bytes().unwrap()));
+    file.extra_field_length = file.extra_field.len() as u16;
+  }
+  central_directory.set_offset_of_start_of_central_directory(bytes.position()).unwrap();
+  central_directory.write(&mut bytes).unwrap();
+  bytes.into_inner()
+}
+
+#[test]
+fn unicode_path_is_the_effective_file_name() {
+  let entry = with_unicode_path(b"Ubersicht.txt", &UnicodePathExtraField::new(b"Ubersicht.txt", "Übersicht.txt"));
+  let unicode_comment = UnicodeCommentExtraField::new(b"Ubersicht", "Übersicht");
+  let bytes = archive(&entry, b"Ubersicht", Some(&unicode_comment));
+
+  let mut options = ParseOptions::strict();
+  let read = Entry::read_with_options(&mut Cursor::new(&bytes), &mut options).unwrap();
+  let central_directory = CentralDirectory::read_from_end_with_options(&mut Cursor::new(&bytes), &mut options).unwrap();
+  assert!(options.diagnostics.is_empty());
+
+  assert_eq!(read.header.decoded_file_name(), "Ubersicht.txt");
+  assert_eq!(read.header.effective_file_name(), "Übersicht.txt");
+  let file = &central_directory.files[0];
+  assert_eq!(file.unicode_path(), read.header.unicode_path());
+  assert_eq!(file.effective_file_name(), "Übersicht.txt");
+  assert_eq!(file.unicode_comment(), Some(unicode_comment));
+  assert_eq!(file.effective_file_comment(), "Übersicht");
+}
+
+#[test]
+fn stale_unicode_fields_are_ignored_and_reported() {
+  // The legacy name and comment were changed after the Unicode fields were written, so their CRCs no longer match.
+  let entry = with_unicode_path(b"renamed.txt", &UnicodePathExtraField::new(b"original.txt", "original.txt"));
+  let bytes = archive(&entry, b"edited", Some(&UnicodeCommentExtraField::new(b"original", "original")));
+
+  let mut options = ParseOptions::strict();
+  let read = Entry::read_with_options(&mut Cursor::new(&bytes), &mut options).unwrap();
+  let central_directory = CentralDirectory::read_from_end_with_options(&mut Cursor::new(&bytes), &mut options).unwrap();
+
+  assert_eq!(read.header.effective_file_name(), "renamed.txt");
+  assert_eq!(central_directory.files[0].effective_file_name(), "renamed.txt");
+  assert_eq!(central_directory.files[0].effective_file_comment(), "edited");
+  let errors: Vec<&Error> = options.diagnostics.iter().map(|diagnostic| &diagnostic.error).collect();
+  assert!(matches!(
+    errors.as_slice(),
+    [Error::MismatchedUnicodePath, Error::MismatchedUnicodePath, Error::MismatchedUnicodeComment]
+  ));
+}
+
+#[test]
+fn transcoding_replaces_unicode_fields_with_utf8_names() {
+  let entry = with_unicode_path(b"Ubersicht.txt", &UnicodePathExtraField::new(b"Ubersicht.txt", "Übersicht.txt"));
+  let bytes = archive(&entry, b"Ubersicht", Some(&UnicodeCommentExtraField::new(b"Ubersicht", "Übersicht")));
+  let mut central_directory = CentralDirectory::read_from_end(&mut Cursor::new(&bytes)).unwrap();
+
+  central_directory.transcode_file_names(FileNameEncoding::Cp437).unwrap();
+
+  let file = &central_directory.files[0];
+  assert!(file.indicates_utf8());
+  assert_eq!(file.file_name, "Übersicht.txt".as_bytes());
+  assert_eq!(file.file_comment, "Übersicht".as_bytes());
+  assert_eq!(file.unicode_path(), None);
+  assert_eq!(file.unicode_comment(), None);
+}
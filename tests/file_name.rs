@@ -0,0 +1,71 @@
+use std::io::Cursor;
+use synthzip::{CentralDirectory, CompressionMethod, Entry, FileNameEncoding, ParseOptions};
+
+fn with_file_name(file_name: &[u8], utf8: bool) -> Entry {
+  let mut entry = Entry::new(file_name.to_vec(), b"contents", CompressionMethod::Stored).unwrap();
+  if utf8 {
+    entry.header.general_purpose_flags |= 0b0000100000000000;
+  } else {
+    entry.header.general_purpose_flags &= !0b0000100000000000;
+  }
+  entry
+}
+
+fn round_trip(entry: &Entry, options: &mut ParseOptions) -> (Vec<u8>, Entry, CentralDirectory) {
+  let mut bytes = Cursor::new(Vec::new());
+  entry.write(&mut bytes).unwrap();
+  let mut central_directory = CentralDirectory::new();
+  central_directory.add(entry).unwrap();
+  central_directory.write(&mut bytes).unwrap();
+  let bytes = bytes.into_inner();
+
+  let read = Entry::read_with_options(&mut Cursor::new(&bytes), options).unwrap();
+  let central_directory = CentralDirectory::read_from_end_with_options(&mut Cursor::new(&bytes), options).unwrap();
+  (bytes, read, central_directory)
+}
+
+#[test]
+fn legacy_file_name_falls_back_to_cp437() {
+  // "Übersicht ½.txt" as written by DOS-era tools.
+  let file_name = b"\x9Abersicht \xAB.txt";
+  let entry = with_file_name(file_name, false);
+
+  let (_, read, central_directory) = round_trip(&entry, &mut ParseOptions::default());
+
+  assert_eq!(read.header.file_name, file_name);
+  assert_eq!(read.header.decoded_file_name(), "Übersicht ½.txt");
+  assert_eq!(central_directory.files[0].file_name, file_name);
+  assert_eq!(central_directory.files[0].decoded_file_name(), "Übersicht ½.txt");
+}
+
+#[test]
+fn utf8_flag_decodes_file_name_as_utf8() {
+  let entry = with_file_name("Übersicht ½.txt".as_bytes(), true);
+
+  let (_, read, central_directory) = round_trip(&entry, &mut ParseOptions::default());
+
+  assert_eq!(read.header.decoded_file_name(), "Übersicht ½.txt");
+  assert_eq!(central_directory.files[0].decoded_file_name(), "Übersicht ½.txt");
+  // The same bytes without the flag are read as CP437.
+  assert_eq!(with_file_name("Ü.txt".as_bytes(), false).header.decoded_file_name(), "├£.txt");
+}
+
+#[test]
+fn raw_file_name_bytes_are_written_back_exactly() {
+  for (file_name, utf8) in [(&b"\x9Abersicht.txt"[..], false), (b"x\xFF\xFE.txt", true), (b"\x82\xA0.txt", false)] {
+    let entry = with_file_name(file_name, utf8);
+    let (bytes, read, central_directory) = round_trip(&entry, &mut ParseOptions::lenient());
+
+    assert_eq!(read, entry);
+    assert_eq!(central_directory.files[0].file_name, file_name);
+    assert_eq!(
+      central_directory.files[0].decoded_file_name_with(FileNameEncoding::Cp437),
+      read.header.decoded_file_name_with(FileNameEncoding::Cp437)
+    );
+
+    let mut rewritten = Cursor::new(Vec::new());
+    read.write(&mut rewritten).unwrap();
+    central_directory.write(&mut rewritten).unwrap();
+    assert_eq!(rewritten.into_inner(), bytes);
+  }
+}
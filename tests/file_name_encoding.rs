@@ -0,0 +1,93 @@
+use std::io::Cursor;
+use synthzip::{CentralDirectory, CompressionMethod, Entry, Error, FileNameEncoding, ParseOptions, Scan};
+
+fn encode(encoding: &'static encoding_rs::Encoding, file_name: &str) -> Vec<u8> {
+  encoding.encode(file_name).0.into_owned()
+}
+
+#[test]
+fn single_japanese_names_are_detected_as_shift_jis() {
+  for file_name in ["日本語.txt", "東京.txt", "報告書2023.docx", "テスト.txt", "会議資料.pdf", "写真/旅行.jpg"] {
+    let detection = FileNameEncoding::detect([encode(encoding_rs::SHIFT_JIS, file_name).as_slice()]);
+    assert_eq!(detection.encoding, FileNameEncoding::ShiftJis, "{file_name}");
+    assert!(detection.confidence > 0.0, "{file_name}");
+  }
+}
+
+#[test]
+fn names_are_detected_in_their_own_encoding() {
+  for (encoding, expected, file_names) in [
+    (encoding_rs::GBK, FileNameEncoding::Gbk, ["中文.txt", "图片/旅行.jpg", "说明.txt"]),
+    (encoding_rs::EUC_KR, FileNameEncoding::EucKr, ["한국어.txt", "문서.txt", "보고서.hwp"]),
+    (encoding_rs::IBM866, FileNameEncoding::Cp866, ["Привет.txt", "документ.doc", "Новая папка"]),
+  ] {
+    let file_names: Vec<Vec<u8>> = file_names.iter().map(|file_name| encode(encoding, file_name)).collect();
+    let detection = FileNameEncoding::detect(file_names.iter().map(Vec::as_slice));
+    assert_eq!(detection.encoding, expected);
+  }
+
+  assert_eq!(FileNameEncoding::detect([&b"plain.txt"[..]]).encoding, FileNameEncoding::Cp437);
+  assert_eq!(FileNameEncoding::detect(["naïve.txt".as_bytes()]).encoding, FileNameEncoding::Utf8);
+}
+
+#[test]
+fn forced_encoding_decodes_legacy_names() {
+  let file_name = encode(encoding_rs::SHIFT_JIS, "日本語.txt");
+  let entry = Entry::new(file_name, b"", CompressionMethod::Stored).unwrap();
+
+  assert_eq!(entry.header.decoded_file_name_with(FileNameEncoding::ShiftJis), "日本語.txt");
+  assert_eq!(entry.header.decoded_file_name_with(FileNameEncoding::Auto), "日本語.txt");
+  assert_ne!(entry.header.decoded_file_name(), "日本語.txt");
+}
+
+#[test]
+fn expected_encoding_only_decides_what_is_undecodable() {
+  // 0xA0 is a valid CP437 byte but never a valid Shift_JIS one.
+  let mut bytes = Cursor::new(Vec::new());
+  Entry::new(b"\xA0.txt".to_vec(), b"", CompressionMethod::Stored).unwrap().write(&mut bytes).unwrap();
+
+  let mut options = ParseOptions::strict();
+  options.expected_file_name_encoding = FileNameEncoding::ShiftJis;
+  bytes.set_position(0);
+  assert!(matches!(
+    Entry::read_with_options(&mut bytes, &mut options),
+    Err(Error::UndecodableFileName(FileNameEncoding::ShiftJis))
+  ));
+
+  bytes.set_position(0);
+  let entry = Entry::read(&mut bytes).unwrap();
+  assert_eq!(entry.header.decoded_file_name(), "á.txt");
+}
+
+#[test]
+fn transcoding_rewrites_local_and_central_names() {
+  let mut bytes = Cursor::new(Vec::new());
+  for file_name in ["日本語.txt", "東京.txt"] {
+    Entry::new(encode(encoding_rs::SHIFT_JIS, file_name), file_name.as_bytes(), CompressionMethod::Deflated)
+      .unwrap()
+      .write(&mut bytes)
+      .unwrap();
+  }
+  bytes.set_position(0);
+
+  let mut scan = Scan::read(&mut bytes).unwrap();
+  scan.transcode_file_names(FileNameEncoding::Auto).unwrap();
+
+  let mut archive = Cursor::new(Vec::new());
+  for entry in &scan.entries {
+    entry.write(&mut archive).unwrap();
+  }
+  scan.central_directory.write(&mut archive).unwrap();
+
+  let central_directory = CentralDirectory::read_from_end(&mut archive).unwrap();
+  for (file, expected) in central_directory.files.iter().zip(["日本語.txt", "東京.txt"]) {
+    assert!(file.indicates_utf8());
+    assert_eq!(file.file_name, expected.as_bytes());
+
+    archive.set_position(file.effective_relative_offset_of_local_file_header());
+    let entry = Entry::read(&mut archive).unwrap();
+    assert!(entry.header.indicates_utf8());
+    assert_eq!(entry.header.file_name, expected.as_bytes());
+    assert_eq!(entry.decompress().unwrap(), expected.as_bytes());
+  }
+}
@@ -0,0 +1,72 @@
+use std::io::{Cursor, Read, Write};
+use synthzip::{CompressionMethod, DataDescriptor, Entry, LazyEntry, Scan};
+
+const CONTENTS: [(&str, &[u8], CompressionMethod); 3] = [
+  ("stored.txt", b"stored", CompressionMethod::Stored),
+  ("streamed.txt", b"streamed streamed streamed", CompressionMethod::Deflated),
+  ("bzip2.txt", b"bzip2 bzip2 bzip2", CompressionMethod::Bzip2),
+];
+
+fn stream() -> Vec<u8> {
+  let mut bytes = Cursor::new(Vec::new());
+  for (file_name, contents, compression_method) in CONTENTS {
+    let mut entry = Entry::new(file_name.as_bytes().to_vec(), contents, compression_method).unwrap();
+    if file_name != "streamed.txt" {
+      entry.write(&mut bytes).unwrap();
+      continue;
+    }
+
+    let data_descriptor = DataDescriptor {
+      signature: Some(DataDescriptor::SIGNATURE),
+      crc32_of_uncompressed_data: entry.header.crc32_of_uncompressed_data,
+      compressed_size: entry.data.len() as u64,
+      uncompressed_size: contents.len() as u64,
+      zip64: false,
+    };
+    entry.header.general_purpose_flags |= 0b00001000;
+    entry.header.crc32_of_uncompressed_data = 0;
+    entry.header.set_sizes(0, 0).unwrap();
+    entry.header.write(&mut bytes).unwrap();
+    bytes.write_all(&entry.data).unwrap();
+    data_descriptor.write(&mut bytes).unwrap();
+  }
+  bytes.into_inner()
+}
+
+#[test]
+fn lazy_scan_matches_eager_scan() {
+  let bytes = stream();
+  let eager = Scan::read(&mut Cursor::new(&bytes)).unwrap();
+  let mut reader = Cursor::new(&bytes);
+  let lazy = Scan::read_lazy(&mut reader).unwrap();
+
+  assert_eq!(lazy.central_directory, eager.central_directory);
+  assert_eq!(lazy.stop, eager.stop);
+  assert_eq!(lazy.entries.len(), CONTENTS.len());
+
+  for (lazy, eager) in lazy.entries.iter().zip(&eager.entries) {
+    assert_eq!(lazy.load(&mut reader).unwrap(), *eager);
+
+    let mut raw = Vec::new();
+    lazy.raw(&mut reader).unwrap().read_to_end(&mut raw).unwrap();
+    assert_eq!(raw, eager.data);
+  }
+}
+
+#[test]
+fn lazy_entry_decompresses_on_demand() {
+  let bytes = stream();
+  let mut reader = Cursor::new(&bytes);
+
+  for (_, contents, _) in CONTENTS {
+    let entry = LazyEntry::read(&mut reader).unwrap();
+    let next = reader.position();
+
+    let mut uncompressed = Vec::new();
+    entry.decompressor(&mut reader).unwrap().read_to_end(&mut uncompressed).unwrap();
+    assert_eq!(uncompressed, contents);
+
+    reader.set_position(next);
+  }
+  assert_eq!(reader.position(), bytes.len() as u64);
+}
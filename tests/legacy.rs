@@ -0,0 +1,49 @@
+use std::io::Cursor;
+use synthzip::{CompressionMethod, Error, Scan};
+
+// One text compressed with every legacy method. Info-ZIP's unzip accepts the Shrunk and Imploded entries;
+// it can't expand Reduced data, so those were checked with a separate expander written from the APPNOTE.
+const LEGACY: &[u8] = include_bytes!("fixtures/legacy.zip");
+const CRC32: u32 = 0xEC21AF92;
+const SIZE: usize = 1325;
+
+#[test]
+fn expands_known_good_legacy_entries() {
+  let scan = Scan::read(&mut Cursor::new(LEGACY)).unwrap();
+  let names: Vec<String> = scan.entries.iter().map(|entry| entry.header.decoded_file_name()).collect();
+  assert_eq!(
+    names,
+    [
+      "shrunk.txt",
+      "reduced1.txt",
+      "reduced2.txt",
+      "reduced3.txt",
+      "reduced4.txt",
+      "imploded0.txt",
+      "imploded2.txt",
+      "imploded4.txt",
+      "imploded6.txt"
+    ]
+  );
+
+  for entry in &scan.entries {
+    let uncompressed = entry.decompress().unwrap();
+    assert_eq!(uncompressed.len(), SIZE, "{}", entry.header.decoded_file_name());
+    assert_eq!(crc32fast::hash(&uncompressed), CRC32, "{}", entry.header.decoded_file_name());
+    assert_eq!(entry.header.crc32_of_uncompressed_data, CRC32);
+  }
+}
+
+#[test]
+fn reduced_and_imploded_data_needs_the_uncompressed_size() {
+  let scan = Scan::read(&mut Cursor::new(LEGACY)).unwrap();
+
+  for entry in &scan.entries {
+    let compression_method = CompressionMethod::from(entry.header.compression_method);
+    let result = compression_method.decoder(entry.data.as_slice());
+    match compression_method {
+      CompressionMethod::Shrunk => assert!(result.is_ok()),
+      _ => assert!(matches!(result, Err(Error::MissingUncompressedSize(method)) if method == entry.header.compression_method)),
+    }
+  }
+}
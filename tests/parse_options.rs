@@ -0,0 +1,130 @@
+use std::io::{Cursor, Write};
+use synthzip::{CentralDirectory, CompressionMethod, DataDescriptor, Entry, Error, FileNameEncoding, ParseOptions, Scan};
+
+const CONTENTS: &[u8] = b"conflicted conflicted conflicted";
+
+fn conflicting_crc32() -> Vec<u8> {
+  let mut entry = Entry::new(b"conflicted.txt".to_vec(), CONTENTS, CompressionMethod::Deflated).unwrap();
+  let data_descriptor = DataDescriptor {
+    signature: Some(DataDescriptor::SIGNATURE),
+    crc32_of_uncompressed_data: entry.header.crc32_of_uncompressed_data,
+    compressed_size: entry.data.len() as u64,
+    uncompressed_size: CONTENTS.len() as u64,
+    zip64: false,
+  };
+  entry.header.general_purpose_flags |= 0b00001000;
+  entry.header.crc32_of_uncompressed_data ^= 0xFFFF;
+
+  let mut bytes = Cursor::new(Vec::new());
+  entry.header.write(&mut bytes).unwrap();
+  bytes.write_all(&entry.data).unwrap();
+  data_descriptor.write(&mut bytes).unwrap();
+  bytes.into_inner()
+}
+
+fn has_conflict(options: &ParseOptions) -> bool {
+  options
+    .diagnostics
+    .iter()
+    .any(|diagnostic| matches!(diagnostic.error, Error::DataDescriptorConflictsWithLocalFileHeader))
+}
+
+#[test]
+fn data_descriptor_conflict_fails_in_strict_mode() {
+  let result = Entry::read_with_options(&mut Cursor::new(conflicting_crc32()), &mut ParseOptions::strict());
+
+  assert!(matches!(result, Err(Error::DataDescriptorConflictsWithLocalFileHeader)));
+}
+
+#[test]
+fn data_descriptor_conflict_is_recorded_in_lenient_mode() {
+  let mut options = ParseOptions::lenient();
+  let entry = Entry::read_with_options(&mut Cursor::new(conflicting_crc32()), &mut options).unwrap();
+
+  assert!(has_conflict(&options));
+  assert_eq!(entry.decompress().unwrap(), CONTENTS);
+  CentralDirectory::new().add(&entry).unwrap();
+}
+
+#[test]
+fn scan_accepts_parse_options() {
+  assert!(Scan::read(&mut Cursor::new(conflicting_crc32())).is_err());
+
+  let mut options = ParseOptions::lenient();
+  let scan = Scan::read_with_options(&mut Cursor::new(conflicting_crc32()), &mut options).unwrap();
+
+  assert_eq!(scan.entries.len(), 1);
+  assert_eq!(scan.central_directory.files.len(), 1);
+  assert!(has_conflict(&options));
+}
+
+#[test]
+fn central_directory_accepts_parse_options() {
+  let entry = Entry::new(b"file.txt".to_vec(), b"contents", CompressionMethod::Stored).unwrap();
+  let mut central_directory = CentralDirectory::new();
+  let mut bytes = Cursor::new(Vec::new());
+  entry.write(&mut bytes).unwrap();
+  central_directory.add(&entry).unwrap();
+  central_directory.write(&mut bytes).unwrap();
+  let mut bytes = bytes.into_inner();
+  let start_of_central_directory = central_directory.offset_of_start_of_central_directory() as usize;
+  bytes[start_of_central_directory + 3] = 0xFF;
+
+  assert!(CentralDirectory::read_from_end(&mut Cursor::new(&bytes)).is_err());
+
+  let mut options = ParseOptions::lenient();
+  let read = CentralDirectory::read_from_end_with_options(&mut Cursor::new(&bytes), &mut options).unwrap();
+
+  assert_eq!(read.files.len(), 1);
+  assert!(options
+    .diagnostics
+    .iter()
+    .any(|diagnostic| matches!(diagnostic.error, Error::BadSignatureInCentralDirectoryFileHeader)));
+}
+
+fn invalid_utf8_file_name() -> Vec<u8> {
+  let mut entry = Entry::new(b"x".to_vec(), CONTENTS, CompressionMethod::Stored).unwrap();
+  entry.header.general_purpose_flags |= 0b0000100000000000;
+  entry.header.file_name = vec![b'x', 0xFF, 0xFE];
+  entry.header.file_name_length = 3;
+
+  let mut bytes = Cursor::new(Vec::new());
+  entry.write(&mut bytes).unwrap();
+  let mut central_directory = CentralDirectory::new();
+  central_directory.add(&entry).unwrap();
+  central_directory.write(&mut bytes).unwrap();
+  bytes.into_inner()
+}
+
+#[test]
+fn undecodable_file_name_fails_in_strict_mode() {
+  let bytes = invalid_utf8_file_name();
+
+  assert!(matches!(
+    Entry::read(&mut Cursor::new(&bytes)),
+    Err(Error::UndecodableFileName(FileNameEncoding::Utf8))
+  ));
+  assert!(matches!(
+    CentralDirectory::read_from_end(&mut Cursor::new(&bytes)),
+    Err(Error::UndecodableFileName(FileNameEncoding::Utf8))
+  ));
+}
+
+#[test]
+fn undecodable_file_name_is_recorded_in_lenient_mode() {
+  let bytes = invalid_utf8_file_name();
+  let mut options = ParseOptions::lenient();
+
+  let entry = Entry::read_with_options(&mut Cursor::new(&bytes), &mut options).unwrap();
+  let central_directory = CentralDirectory::read_from_end_with_options(&mut Cursor::new(&bytes), &mut options).unwrap();
+
+  assert_eq!(entry.header.file_name, central_directory.files[0].file_name);
+  assert_eq!(
+    options
+      .diagnostics
+      .iter()
+      .filter(|diagnostic| matches!(diagnostic.error, Error::UndecodableFileName(FileNameEncoding::Utf8)))
+      .count(),
+    2
+  );
+}
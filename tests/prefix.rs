@@ -0,0 +1,84 @@
+use std::io::Cursor;
+use synthzip::{CentralDirectory, CompressionMethod, Entry, Offsets, Repair, Scan};
+
+const STUB: &[u8] = b"MZ\x90\x00 a self-extractor stub that is not part of the archive";
+
+fn archive() -> (Vec<u8>, Vec<Entry>) {
+  let entries: Vec<Entry> = [("first.txt", &b"first"[..]), ("second.txt", b"second second")]
+    .into_iter()
+    .map(|(file_name, contents)| Entry::new(file_name.as_bytes().to_vec(), contents, CompressionMethod::Deflated).unwrap())
+    .collect();
+
+  let mut archive = Cursor::new(Vec::new());
+  let mut central_directory = CentralDirectory::new();
+  for entry in &entries {
+    entry.write(&mut archive).unwrap();
+    central_directory.add(entry).unwrap();
+  }
+  central_directory.write(&mut archive).unwrap();
+  (archive.into_inner(), entries)
+}
+
+fn read_entries(bytes: &[u8]) -> (u64, Vec<Entry>) {
+  let mut reader = Cursor::new(bytes);
+  let central_directory = CentralDirectory::read_from_end(&mut reader).unwrap();
+  let entries = central_directory
+    .files
+    .iter()
+    .map(|file| {
+      reader.set_position(central_directory.prefix_length + file.effective_relative_offset_of_local_file_header());
+      Entry::read(&mut reader).unwrap()
+    })
+    .collect();
+  (central_directory.prefix_length, entries)
+}
+
+#[test]
+fn relative_offsets_behind_a_stub_record_the_prefix() {
+  let (archive, entries) = archive();
+  let bytes = [STUB, &archive].concat();
+
+  assert_eq!(read_entries(&bytes), (STUB.len() as u64, entries));
+}
+
+#[test]
+fn absolute_offsets_behind_a_stub_need_no_prefix() {
+  let (archive, entries) = archive();
+  let mut central_directory = CentralDirectory::read_from_end(&mut Cursor::new(&archive)).unwrap();
+  central_directory.prefix_length = STUB.len() as u64;
+
+  let mut bytes = Cursor::new([STUB, &archive[..central_directory.offset_of_start_of_central_directory() as usize]].concat());
+  bytes.set_position(bytes.get_ref().len() as u64);
+  central_directory.write_with_offsets(&mut bytes, Offsets::Absolute).unwrap();
+
+  assert_eq!(read_entries(bytes.get_ref()), (0, entries));
+}
+
+#[test]
+fn scan_skips_and_records_the_prefix() {
+  let (archive, entries) = archive();
+  let bytes = [STUB, &archive].concat();
+
+  let scan = Scan::read(&mut Cursor::new(&bytes)).unwrap();
+  assert_eq!(scan.entries, entries);
+  assert_eq!(scan.central_directory.prefix_length, STUB.len() as u64);
+  assert_eq!(scan.central_directory, CentralDirectory::read_from_end(&mut Cursor::new(&bytes)).unwrap());
+}
+
+#[test]
+fn repair_strips_or_keeps_the_prefix() {
+  let (archive, entries) = archive();
+  let bytes = [STUB, &archive].concat();
+  let repair = Repair::read(&mut Cursor::new(&bytes)).unwrap();
+  assert_eq!(repair.prefix, STUB);
+
+  let mut relative = Cursor::new(Vec::new());
+  repair.write_with_offsets(&mut relative, Offsets::Relative).unwrap();
+  assert_eq!(relative.into_inner(), archive);
+
+  let mut absolute = Cursor::new(Vec::new());
+  repair.write_with_offsets(&mut absolute, Offsets::Absolute).unwrap();
+  let absolute = absolute.into_inner();
+  assert!(absolute.starts_with(STUB));
+  assert_eq!(read_entries(&absolute), (0, entries));
+}
@@ -0,0 +1,131 @@
+use std::io::Cursor;
+use synthzip::{CentralDirectory, CompressionMethod, Entry, Repair};
+
+const CONTENTS: [(&str, &[u8]); 3] = [("first.txt", b"first"), ("second.txt", b"second second"), ("third.txt", b"third third third")];
+
+fn archive() -> (Vec<u8>, u64) {
+  let mut archive = Cursor::new(Vec::new());
+  let mut central_directory = CentralDirectory::new();
+  for (file_name, contents) in CONTENTS {
+    let entry = Entry::new(file_name.as_bytes().to_vec(), contents, CompressionMethod::Deflated).unwrap();
+    entry.write(&mut archive).unwrap();
+    central_directory.add(&entry).unwrap();
+    let file = central_directory.files.last_mut().unwrap();
+    file.external_file_attributes = 0o100644 << 16;
+    file.set_file_comment(format!("comment on {file_name}").into_bytes()).unwrap();
+  }
+  let start_of_central_directory = archive.position();
+  central_directory.set_offset_of_start_of_central_directory(start_of_central_directory).unwrap();
+  central_directory.write(&mut archive).unwrap();
+  (archive.into_inner(), start_of_central_directory)
+}
+
+fn read_back(archive: &[u8]) -> Vec<(String, Vec<u8>, String)> {
+  let mut reader = Cursor::new(archive);
+  let central_directory = CentralDirectory::read_from_end(&mut reader).unwrap();
+  central_directory
+    .files
+    .iter()
+    .map(|file| {
+      reader.set_position(file.effective_relative_offset_of_local_file_header());
+      let entry = Entry::read(&mut reader).unwrap();
+      (
+        file.decoded_file_name(),
+        entry.decompress().unwrap(),
+        String::from_utf8(file.file_comment.clone()).unwrap(),
+      )
+    })
+    .collect()
+}
+
+fn repaired(archive: &[u8]) -> (Repair, Vec<u8>) {
+  let repair = Repair::read(&mut Cursor::new(archive)).unwrap();
+  let mut output = Cursor::new(Vec::new());
+  repair.write(&mut output).unwrap();
+  (repair, output.into_inner())
+}
+
+fn expected() -> Vec<(String, Vec<u8>, String)> {
+  CONTENTS
+    .iter()
+    .map(|(file_name, contents)| (file_name.to_string(), contents.to_vec(), format!("comment on {file_name}")))
+    .collect()
+}
+
+#[test]
+fn repair_keeps_an_intact_central_directory() {
+  let (bytes, _) = archive();
+  let (repair, output) = repaired(&bytes);
+
+  assert!(repair.unmatched.is_empty());
+  assert_eq!(output, bytes);
+}
+
+#[test]
+fn repair_fixes_wrong_offsets_and_keeps_metadata() {
+  let (bytes, _) = archive();
+  let mut reader = Cursor::new(&bytes);
+  let mut central_directory = CentralDirectory::read_from_end(&mut reader).unwrap();
+  for file in &mut central_directory.files {
+    file.relative_offset_of_local_file_header += 3;
+  }
+  let mut damaged = Cursor::new(bytes[..central_directory.offset_of_start_of_central_directory() as usize].to_vec());
+  damaged.set_position(damaged.get_ref().len() as u64);
+  central_directory.write(&mut damaged).unwrap();
+
+  let (repair, output) = repaired(damaged.get_ref());
+
+  assert!(repair.unmatched.is_empty());
+  assert!(repair.central_directory.files.iter().all(|file| file.external_file_attributes == 0o100644 << 16));
+  assert_eq!(read_back(&output), expected());
+  assert_eq!(output, bytes);
+}
+
+#[test]
+fn repair_recovers_a_truncated_central_directory() {
+  let (bytes, start_of_central_directory) = archive();
+  let first_file_header = CentralDirectory::read_from_end(&mut Cursor::new(&bytes)).unwrap().files[0].clone();
+  let truncated = &bytes[..start_of_central_directory as usize + 46 + first_file_header.file_name.len() + first_file_header.file_comment.len() + 10];
+
+  let (repair, output) = repaired(truncated);
+
+  let mut expected = expected();
+  for (_, _, comment) in &mut expected[1..] {
+    comment.clear();
+  }
+  assert_eq!(read_back(&output), expected);
+  assert_eq!(repair.entries.len(), CONTENTS.len());
+}
+
+#[test]
+fn repair_rebuilds_a_zeroed_central_directory() {
+  let (mut bytes, start_of_central_directory) = archive();
+  bytes[start_of_central_directory as usize..].fill(0);
+
+  let (repair, output) = repaired(&bytes);
+
+  assert_eq!(repair.entries.len(), CONTENTS.len());
+  let names: Vec<String> = read_back(&output).into_iter().map(|(file_name, _, _)| file_name).collect();
+  assert_eq!(names, CONTENTS.map(|(file_name, _)| file_name));
+}
+
+#[test]
+fn repair_reports_central_directory_headers_without_local_entries() {
+  let (bytes, start_of_central_directory) = archive();
+  let mut central_directory = CentralDirectory::read_from_end(&mut Cursor::new(&bytes)).unwrap();
+  let mut orphan = central_directory.files[0].clone();
+  orphan.set_file_name(b"orphan.txt".to_vec()).unwrap();
+  central_directory.files.push(orphan);
+  central_directory.set_offset_of_start_of_central_directory(start_of_central_directory).unwrap();
+  central_directory.end.total_number_of_central_directory_records += 1;
+  central_directory.end.number_of_central_directory_records_on_this_disk += 1;
+  let mut archive = Cursor::new(bytes[..start_of_central_directory as usize].to_vec());
+  archive.set_position(start_of_central_directory);
+  central_directory.write(&mut archive).unwrap();
+
+  let (repair, output) = repaired(archive.get_ref());
+
+  assert_eq!(repair.unmatched.len(), 1);
+  assert_eq!(repair.unmatched[0].file_name, b"orphan.txt");
+  assert_eq!(read_back(&output), expected());
+}
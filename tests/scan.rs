@@ -0,0 +1,231 @@
+use std::io::{Cursor, Write};
+use synthzip::{CentralDirectory, CentralDirectoryFileHeader, CompressionMethod, DataDescriptor, Entry, Incomplete, LocalEntry, Repair, Scan, Stop};
+
+fn write_streamed(bytes: &mut Cursor<Vec<u8>>, file_name: &str, contents: &[u8], compression_method: CompressionMethod, data_descriptor: bool) -> u64 {
+  let position = bytes.position();
+  let mut entry = Entry::new(file_name.as_bytes().to_vec(), contents, compression_method).unwrap();
+  let data_descriptor = data_descriptor.then_some(DataDescriptor {
+    signature: Some(DataDescriptor::SIGNATURE),
+    crc32_of_uncompressed_data: entry.header.crc32_of_uncompressed_data,
+    compressed_size: entry.data.len() as u64,
+    uncompressed_size: contents.len() as u64,
+    zip64: false,
+  });
+  entry.header.general_purpose_flags |= 0b00001000;
+  entry.header.crc32_of_uncompressed_data = 0;
+  entry.header.set_sizes(0, 0).unwrap();
+
+  entry.header.write(bytes).unwrap();
+  bytes.write_all(&entry.data).unwrap();
+  if let Some(data_descriptor) = data_descriptor {
+    data_descriptor.write(bytes).unwrap();
+  }
+  position
+}
+
+fn stream_deflate() -> (Vec<u8>, u64) {
+  let mut bytes = Cursor::new(Vec::new());
+  write_streamed(&mut bytes, "one.txt", b"one one one one", CompressionMethod::Deflated, true);
+  write_streamed(&mut bytes, "two.txt", b"two two two two", CompressionMethod::Deflated, true);
+  let third = write_streamed(&mut bytes, "three.txt", b"three three three", CompressionMethod::Deflated, true);
+  (bytes.into_inner(), third)
+}
+
+fn rewrite(scan: &Scan) -> Vec<u8> {
+  let mut output = Cursor::new(Vec::new());
+  for entry in &scan.entries {
+    entry.write(&mut output).unwrap();
+  }
+  scan.central_directory.write(&mut output).unwrap();
+  output.into_inner()
+}
+
+fn read_back(archive: &[u8]) -> Vec<(String, Vec<u8>)> {
+  let mut reader = Cursor::new(archive);
+  let central_directory = CentralDirectory::read_from_end(&mut reader).unwrap();
+  central_directory
+    .files
+    .iter()
+    .map(|file| {
+      reader.set_position(central_directory.prefix_length + file.effective_relative_offset_of_local_file_header());
+      let entry = Entry::read(&mut reader).unwrap();
+      assert_eq!(entry.header.file_name, file.file_name);
+      (file.decoded_file_name(), entry.decompress().unwrap())
+    })
+    .collect()
+}
+
+fn concatenated() -> Vec<u8> {
+  let mut bytes = Cursor::new(Vec::new());
+  for (file_name, contents, compression_method) in [
+    ("stored.txt", &b"stored"[..], CompressionMethod::Stored),
+    ("deflated.txt", b"deflated deflated deflated", CompressionMethod::Deflated),
+    ("bzip2.txt", b"bzip2 bzip2 bzip2", CompressionMethod::Bzip2),
+  ] {
+    Entry::new(file_name.as_bytes().to_vec(), contents, compression_method)
+      .unwrap()
+      .write(&mut bytes)
+      .unwrap();
+  }
+  bytes.into_inner()
+}
+
+#[test]
+fn scan_builds_central_directory_for_concatenated_entries() {
+  let bytes = concatenated();
+
+  let scan = Scan::read(&mut Cursor::new(&bytes)).unwrap();
+  assert_eq!(scan.stop, Stop::EndOfFile);
+  assert_eq!(scan.entries.len(), 3);
+  assert_eq!(scan.central_directory.offset_of_start_of_central_directory(), bytes.len() as u64);
+  let offsets: Vec<u64> = scan
+    .central_directory
+    .files
+    .iter()
+    .map(CentralDirectoryFileHeader::effective_relative_offset_of_local_file_header)
+    .collect();
+  let first = scan.entries[0].size_in_archive();
+  let second = scan.entries[1].size_in_archive();
+  assert_eq!(offsets, [0, first, first + second]);
+
+  assert_eq!(
+    read_back(&rewrite(&scan)),
+    [
+      ("stored.txt".to_string(), b"stored".to_vec()),
+      ("deflated.txt".to_string(), b"deflated deflated deflated".to_vec()),
+      ("bzip2.txt".to_string(), b"bzip2 bzip2 bzip2".to_vec()),
+    ]
+  );
+}
+
+#[test]
+fn scan_stops_at_an_existing_central_directory() {
+  let bytes = concatenated();
+  let scan = Scan::read(&mut Cursor::new(&bytes)).unwrap();
+  let archive = rewrite(&scan);
+
+  let rescan = Scan::read(&mut Cursor::new(&archive)).unwrap();
+  assert_eq!(rescan.stop, Stop::Signature(CentralDirectoryFileHeader::SIGNATURE));
+  assert_eq!(rescan.central_directory, scan.central_directory);
+}
+
+#[test]
+fn truncated_header_ends_partial_scan() {
+  let (bytes, third) = stream_deflate();
+
+  for cut in [third + 10, third + 33] {
+    let truncated = &bytes[..cut as usize];
+
+    for incomplete in [Incomplete::Include, Incomplete::Exclude] {
+      let scan = Scan::read_partial(&mut Cursor::new(truncated), incomplete).unwrap();
+      assert_eq!(scan.entries.len(), 2);
+      assert_eq!(
+        scan.stop,
+        Stop::Truncated {
+          remaining: (cut - third) as usize
+        }
+      );
+    }
+
+    let repair = Repair::read(&mut Cursor::new(truncated)).unwrap();
+    assert_eq!(repair.entries.len(), 2);
+    assert_eq!(repair.central_directory.files.len(), 2);
+  }
+}
+
+#[test]
+fn missing_data_descriptor_does_not_swallow_later_entries() {
+  let mut bytes = Cursor::new(Vec::new());
+  write_streamed(&mut bytes, "first.txt", b"first", CompressionMethod::Stored, false);
+  let second = Entry::new(b"second.txt".to_vec(), b"second", CompressionMethod::Stored).unwrap();
+  second.write(&mut bytes).unwrap();
+  let bytes = bytes.into_inner();
+
+  let scan = Scan::read_partial(&mut Cursor::new(&bytes), Incomplete::Include).unwrap();
+  assert_eq!(scan.entries.len(), 2);
+  assert_eq!(scan.entries[0].decompress().unwrap(), b"first");
+  assert_eq!(scan.entries[1].decompress().unwrap(), b"second");
+  assert_eq!(scan.stop, Stop::EndOfFile);
+
+  let scan = Scan::read_partial(&mut Cursor::new(&bytes), Incomplete::Exclude).unwrap();
+  assert_eq!(scan.entries.len(), 1);
+  assert_eq!(scan.entries[0].header.file_name, b"second.txt");
+  assert_eq!(scan.stop, Stop::EndOfFile);
+}
+
+#[test]
+fn partial_scan_central_directory_describes_the_returned_entries() {
+  // An excluded entry leaves a gap, and a completed entry is re-encoded to a different size.
+  let mut bytes = Cursor::new(Vec::new());
+  write_streamed(&mut bytes, "excluded.txt", b"excluded", CompressionMethod::Stored, false);
+  Entry::new(b"kept.txt".to_vec(), b"kept", CompressionMethod::Stored)
+    .unwrap()
+    .write(&mut bytes)
+    .unwrap();
+  write_streamed(
+    &mut bytes,
+    "truncated.txt",
+    b"truncated truncated truncated truncated",
+    CompressionMethod::Deflated,
+    true,
+  );
+  let mut bytes = bytes.into_inner();
+  bytes.truncate(bytes.len() - 20);
+
+  let scan = Scan::read_partial(&mut Cursor::new(&bytes), Incomplete::Exclude).unwrap();
+  assert_eq!(scan.entries.len(), 1);
+  let archive = rewrite(&scan);
+  assert_eq!(CentralDirectory::read_from_end(&mut Cursor::new(&archive)).unwrap().prefix_length, 0);
+  assert_eq!(read_back(&archive), [("kept.txt".to_string(), b"kept".to_vec())]);
+
+  let scan = Scan::read_partial(&mut Cursor::new(&bytes), Incomplete::Include).unwrap();
+  assert_eq!(scan.entries.len(), 3);
+  let archive = rewrite(&scan);
+  assert_eq!(CentralDirectory::read_from_end(&mut Cursor::new(&archive)).unwrap().prefix_length, 0);
+  let read = read_back(&archive);
+  assert_eq!(
+    read[..2],
+    [("excluded.txt".to_string(), b"excluded".to_vec()), ("kept.txt".to_string(), b"kept".to_vec())]
+  );
+  assert_eq!(read[2].0, "truncated.txt");
+}
+
+fn lzma_with_end_of_stream_marker(contents: &[u8]) -> Vec<u8> {
+  let mut stream = Vec::new();
+  let options = lzma_rs::compress::Options {
+    unpacked_size: lzma_rs::compress::UnpackedSize::WriteToHeader(None),
+  };
+  lzma_rs::lzma_compress_with_options(&mut &contents[..], &mut stream, &options).unwrap();
+  // ZIP replaces the .lzma header's unpacked size with a version and a properties size.
+  [&[9, 20, 5, 0], &stream[..5], &stream[13..]].concat()
+}
+
+#[test]
+fn lzma_end_of_stream_marker_bounds_streamed_entry() {
+  let contents = b"lzma lzma lzma lzma lzma";
+  let mut entry = Entry::new(b"lzma.txt".to_vec(), contents, CompressionMethod::Stored).unwrap();
+  entry.header.compression_method = CompressionMethod::Lzma.into();
+  entry.header.general_purpose_flags |= 0b00001010;
+  entry.data = lzma_with_end_of_stream_marker(contents);
+  let data_descriptor = DataDescriptor {
+    signature: Some(DataDescriptor::SIGNATURE),
+    crc32_of_uncompressed_data: entry.header.crc32_of_uncompressed_data,
+    compressed_size: entry.data.len() as u64,
+    uncompressed_size: contents.len() as u64,
+    zip64: false,
+  };
+  entry.header.crc32_of_uncompressed_data = 0;
+  entry.header.set_sizes(0, 0).unwrap();
+
+  let mut bytes = Cursor::new(Vec::new());
+  entry.header.write(&mut bytes).unwrap();
+  bytes.write_all(&entry.data).unwrap();
+  data_descriptor.write(&mut bytes).unwrap();
+  write_streamed(&mut bytes, "one.txt", b"one one one one", CompressionMethod::Deflated, true);
+
+  let scan = Scan::read(&mut Cursor::new(bytes.into_inner())).unwrap();
+  assert_eq!(scan.entries.len(), 2);
+  assert_eq!(scan.entries[0].data, entry.data);
+  assert_eq!(scan.entries[0].decompress().unwrap(), contents);
+  assert_eq!(scan.entries[1].decompress().unwrap(), b"one one one one");
+}
@@ -0,0 +1,83 @@
+use std::io::{Cursor, Write};
+use synthzip::{CentralDirectory, CompressionMethod, DataDescriptor, Entry, LocalEntry};
+
+const CONTENTS: &[u8] = b"zip64 zip64 zip64 zip64";
+
+fn with_zip64_local_header(compression_method: CompressionMethod) -> Entry {
+  let mut entry = Entry::new(b"zip64.txt".to_vec(), CONTENTS, compression_method).unwrap();
+  let compressed_size = entry.data.len() as u64;
+  // Once the header carries a ZIP64 extra field, smaller sizes keep using it, as streaming writers do.
+  entry.header.set_sizes(u32::MAX.into(), u32::MAX.into()).unwrap();
+  entry.header.set_sizes(compressed_size, CONTENTS.len() as u64).unwrap();
+  entry
+}
+
+#[test]
+fn zip64_local_header_round_trips() {
+  let entry = with_zip64_local_header(CompressionMethod::Deflated);
+  assert!(entry.header.indicates_zip64());
+  assert_eq!(entry.header.compressed_size, u32::MAX);
+
+  let mut bytes = Cursor::new(Vec::new());
+  entry.write(&mut bytes).unwrap();
+  bytes.set_position(0);
+
+  let read = Entry::read(&mut bytes).unwrap();
+  assert_eq!(read, entry);
+  assert_eq!(read.header.effective_uncompressed_size(), CONTENTS.len() as u64);
+  assert_eq!(read.decompress().unwrap(), CONTENTS);
+}
+
+#[test]
+fn zip64_data_descriptor_round_trips() {
+  let mut entry = with_zip64_local_header(CompressionMethod::Deflated);
+  let data_descriptor = DataDescriptor {
+    signature: Some(DataDescriptor::SIGNATURE),
+    crc32_of_uncompressed_data: entry.header.crc32_of_uncompressed_data,
+    compressed_size: entry.data.len() as u64,
+    uncompressed_size: CONTENTS.len() as u64,
+    zip64: true,
+  };
+  entry.header.general_purpose_flags |= 0b00001000;
+  entry.header.crc32_of_uncompressed_data = 0;
+  entry.header.set_sizes(0, 0).unwrap();
+
+  let mut bytes = Cursor::new(Vec::new());
+  entry.header.write(&mut bytes).unwrap();
+  bytes.write_all(&entry.data).unwrap();
+  data_descriptor.write(&mut bytes).unwrap();
+  let length = bytes.position();
+  bytes.set_position(0);
+
+  let read = Entry::read(&mut bytes).unwrap();
+  assert_eq!(read.data_descriptor, Some(data_descriptor));
+  assert_eq!(read.size_in_archive(), length);
+  assert_eq!(read.decompress().unwrap(), CONTENTS);
+}
+
+#[test]
+fn central_directory_switches_to_zip64_at_the_record_limit() {
+  let entry = Entry::new(b"e".to_vec(), b"", CompressionMethod::Stored).unwrap();
+
+  let mut archive = Cursor::new(Vec::new());
+  let mut central_directory = CentralDirectory::new();
+  for _ in 1..u16::MAX {
+    entry.write(&mut archive).unwrap();
+    central_directory.add(&entry).unwrap();
+  }
+  assert!(central_directory.zip64_end.is_none());
+
+  entry.write(&mut archive).unwrap();
+  central_directory.add(&entry).unwrap();
+  assert!(central_directory.zip64_end.is_some());
+  assert_eq!(central_directory.end.total_number_of_central_directory_records, u16::MAX);
+  central_directory.write(&mut archive).unwrap();
+
+  let read = CentralDirectory::read_from_end(&mut archive).unwrap();
+  assert_eq!(read.total_number_of_central_directory_records(), u64::from(u16::MAX));
+  assert_eq!(read, central_directory);
+
+  let last = read.files.last().unwrap();
+  archive.set_position(last.effective_relative_offset_of_local_file_header());
+  assert_eq!(Entry::read(&mut archive).unwrap(), entry);
+}